@@ -0,0 +1,293 @@
+use crate::world::World;
+use crate::world_data::company_data::CompanyData;
+use log::info;
+
+// Two-sided 95% critical values of Student's t-distribution for 1 to 30 degrees of freedom
+const T_CRITICAL_VALUES: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160,
+    2.145, 2.131, 2.120, 2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056,
+    2.052, 2.048, 2.045, 2.042,
+];
+// Normal approximation for more degrees of freedom
+const Z_CRITICAL_VALUE: f64 = 1.960;
+
+pub struct SummaryStatistics {
+    pub count: usize,
+    pub mean: f64,
+    // Sample standard deviation
+    pub std: f64,
+    // Half-width of the 95% confidence interval of the mean
+    pub confidence_interval: f64,
+}
+
+impl SummaryStatistics {
+    pub fn from_samples(samples: &[f64]) -> SummaryStatistics {
+        let count = samples.len();
+        if count == 0 {
+            return SummaryStatistics {
+                count,
+                mean: 0.0,
+                std: 0.0,
+                confidence_interval: 0.0,
+            };
+        }
+        let mean = samples.iter().fold(0.0, |a, b| a + b) / count as f64;
+        if count == 1 {
+            return SummaryStatistics {
+                count,
+                mean,
+                std: 0.0,
+                confidence_interval: 0.0,
+            };
+        }
+        let variance = samples
+            .iter()
+            .map(|sample| (sample - mean).powi(2))
+            .fold(0.0, |a, b| a + b)
+            / (count - 1) as f64;
+        let std = variance.sqrt();
+        let critical_value = T_CRITICAL_VALUES
+            .get(count - 2)
+            .copied()
+            .unwrap_or(Z_CRITICAL_VALUE);
+        SummaryStatistics {
+            count,
+            mean,
+            std,
+            confidence_interval: critical_value * std / (count as f64).sqrt(),
+        }
+    }
+}
+
+// Outcome of one evaluation episode, indexed by company
+pub struct EpisodeResult {
+    pub seed: u64,
+    pub company_values: Vec<f64>,
+    // Change of the company value during the episode
+    pub profits: Vec<f64>,
+    // Share of the currency volume sold on all markets during the episode
+    pub market_shares: Vec<f64>,
+}
+
+// Swaps the agents of companies with the same id, swapping again restores both worlds
+pub fn swap_agents(world: &mut World, other_world: &mut World) {
+    for company in world.company_data.companies.iter_mut() {
+        if let Some(other_company) = other_world
+            .company_data
+            .companies
+            .iter_mut()
+            .find(|other_company| other_company.id == company.id)
+        {
+            std::mem::swap(&mut company.agent, &mut other_company.agent);
+        }
+    }
+}
+
+// Stored company values are updated before the markets and contracts of a tick are settled
+fn get_company_values(world: &World) -> Vec<f64> {
+    world
+        .company_data
+        .companies
+        .iter()
+        .map(|company| {
+            company.calculate_company_value(
+                &world.market_data,
+                &world.region_data,
+                &world.processor_data,
+            )
+        })
+        .collect()
+}
+
+// Runs the world without training or exploration, so the agents act on their learned policies
+pub fn run_episode(world: &mut World, seed: u64, ticks: usize) -> EpisodeResult {
+    world.seed(seed);
+    let start_tick = world.current_tick;
+    let start_values = get_company_values(world);
+    for _ in 0..ticks {
+        world.tick(false, 0.0);
+    }
+    let company_count = world.company_data.companies.len();
+    let mut sold_volumes = vec![0.0; company_count];
+    let mut total_volume = 0.0;
+    for market_data in world.region_data.get_all_market_data(&world.market_data) {
+        for record in market_data
+            .log
+            .trades
+            .iter()
+            .filter(|record| record.tick >= start_tick)
+        {
+            let volume = record.trade.get_volume();
+            total_volume += volume;
+            if let Some(seller) = record.trade.seller {
+                if let Some(sold_volume) = sold_volumes.get_mut(seller) {
+                    *sold_volume += volume;
+                }
+            }
+        }
+    }
+    let company_values = get_company_values(world);
+    EpisodeResult {
+        seed,
+        profits: company_values
+            .iter()
+            .zip(start_values.iter())
+            .map(|(value, start_value)| value - start_value)
+            .collect(),
+        company_values,
+        market_shares: sold_volumes
+            .iter()
+            .map(|volume| {
+                if total_volume > 0.0 {
+                    volume / total_volume
+                } else {
+                    0.0
+                }
+            })
+            .collect(),
+    }
+}
+
+pub struct EvaluationReport {
+    pub episodes: Vec<EpisodeResult>,
+}
+
+impl Default for EvaluationReport {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EvaluationReport {
+    pub fn new() -> EvaluationReport {
+        EvaluationReport { episodes: vec![] }
+    }
+
+    fn get_statistics(&self, get_metric: impl Fn(&EpisodeResult) -> f64) -> SummaryStatistics {
+        let samples: Vec<f64> = self.episodes.iter().map(get_metric).collect();
+        SummaryStatistics::from_samples(&samples)
+    }
+
+    pub fn get_value_statistics(&self, company: usize) -> SummaryStatistics {
+        self.get_statistics(|episode| episode.company_values[company])
+    }
+
+    pub fn get_profit_statistics(&self, company: usize) -> SummaryStatistics {
+        self.get_statistics(|episode| episode.profits[company])
+    }
+
+    pub fn get_market_share_statistics(&self, company: usize) -> SummaryStatistics {
+        self.get_statistics(|episode| episode.market_shares[company])
+    }
+
+    pub fn print(&self, company_data: &CompanyData) {
+        let format = |statistics: SummaryStatistics| {
+            format!(
+                "{:.2} ± {:.2} (std {:.2})",
+                statistics.mean, statistics.confidence_interval, statistics.std
+            )
+        };
+        info!(
+            "Evaluation over {} episodes, mean ± 95% confidence interval:",
+            self.episodes.len()
+        );
+        for (i, company) in company_data.companies.iter().enumerate() {
+            info!(" - {} ({}):", company.name, company.agent.get_name());
+            info!("   value {}", format(self.get_value_statistics(i)));
+            info!("   profit {}", format(self.get_profit_statistics(i)));
+            let market_share = self.get_market_share_statistics(i);
+            info!(
+                "   market share {:.2}% ± {:.2}% (std {:.2}%)",
+                market_share.mean * 100.0,
+                market_share.confidence_interval * 100.0,
+                market_share.std * 100.0
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::economy::company::Company;
+    use crate::economy::consumer::Consumer;
+    use crate::economy::producer::Producer;
+    use crate::market::offer::UnprocessedOffer;
+    use crate::market::order::UnprocessedOrder;
+    use crate::market::order_type::OrderType;
+    use crate::persistence::Persistence;
+    use crate::reinforcement_learning::action::ActionSpace;
+    use crate::reinforcement_learning::controller::ControllerType;
+    use crate::reinforcement_learning::state::CompanyState;
+    use crate::world_data::market_data::MarketData;
+
+    // One producer and one consumer of water and a company per learning controller
+    fn build_world() -> World {
+        let mut world = World::new();
+        world.resource_data = Persistence::load_from("data/resources.yml");
+        let resource_count = world.resource_data.resources.len();
+        world.market_data = MarketData::new(resource_count);
+        let mut producer = Producer::new();
+        producer.offer_creation_ticks = 1;
+        producer.production.push(UnprocessedOffer {
+            resource: 0,
+            amount: 10.0,
+            price_per_unit: 5.0,
+            time_to_live: 5,
+            order_type: OrderType::Limit,
+        });
+        world.producer_data.producers.push(producer);
+        let mut consumer = Consumer::new();
+        consumer.order_creation_ticks = 1;
+        consumer.consumption.push(UnprocessedOrder {
+            resource: 0,
+            amount: 10.0,
+            max_price_per_unit: 8.0,
+            time_to_live: 5,
+            order_type: OrderType::Limit,
+        });
+        world.consumer_data.consumers.push(consumer);
+        world.actionspace = ActionSpace::new(
+            &Persistence::load_from("data/actions.yml"),
+            resource_count,
+            0,
+            None,
+        );
+        let state_dimensions = CompanyState::new(resource_count).as_f64_vec().len();
+        let controllers = [
+            ControllerType::DeepRL,
+            ControllerType::QLearning,
+            ControllerType::ActorCritic,
+        ];
+        for (i, controller) in controllers.iter().enumerate() {
+            let mut company = Company::new(
+                &format!("Company {}", i),
+                i,
+                resource_count,
+                state_dimensions as i32,
+                world.actionspace.actions.len() as i32,
+                0.9,
+                *controller,
+            );
+            company.currency = 1000.0;
+            company.stock.add_to_stock(0, 20.0);
+            world.company_data.companies.push(company);
+        }
+        world
+    }
+
+    // Hash map order and the agents' exploration must not change the outcome of a seed
+    #[test]
+    fn same_seed_gives_same_report() {
+        // Networks are initialized randomly, so both runs start from the same saved world
+        let saved_world = serde_yaml::to_string(&build_world()).unwrap();
+        let run = || {
+            let mut world: World = serde_yaml::from_str(&saved_world).unwrap();
+            run_episode(&mut world, 7, 100)
+        };
+        let (first, second) = (run(), run());
+        assert_eq!(first.company_values, second.company_values);
+        assert_eq!(first.profits, second.profits);
+        assert_eq!(first.market_shares, second.market_shares);
+    }
+}
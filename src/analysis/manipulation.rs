@@ -0,0 +1,238 @@
+use crate::economy::company::CompanyHandle;
+use crate::economy::resource::ResourceHandle;
+use crate::market::market_log::{ListingEventKind, ListingSide, MarketLog};
+use crate::world::World;
+use crate::world_data::company_data::CompanyData;
+use crate::world_data::resource_data::ResourceData;
+use log::info;
+use std::collections::HashMap;
+
+pub struct ManipulationThresholds {
+    // Share of the traded amount of a resource a single buyer or seller may have
+    pub dominant_share: f64,
+    // Resources with less traded amount are not checked for dominance
+    pub min_traded_amount: f64,
+    // Share of listings removed without any fill
+    pub unfilled_ratio: f64,
+    // Companies with fewer listings are not checked for place-and-expire patterns
+    pub min_listings: usize,
+    // Relative price change between two consecutive trades of a resource
+    pub price_spike: f64,
+}
+
+impl Default for ManipulationThresholds {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ManipulationThresholds {
+    pub fn new() -> ManipulationThresholds {
+        ManipulationThresholds {
+            dominant_share: 0.5,
+            min_traded_amount: 10.0,
+            unfilled_ratio: 0.9,
+            min_listings: 20,
+            price_spike: 0.5,
+        }
+    }
+}
+
+#[derive(Default, Clone)]
+pub struct CompanyManipulationReport {
+    // Wash trades between the company's own orders and offers
+    pub self_trades: usize,
+    pub self_trade_volume: f64,
+    // Resources and the share of their traded amount the company bought or sold
+    pub dominant_resources: Vec<(ResourceHandle, f64)>,
+    // Listings placed and listings cancelled or expired without any fill
+    pub placed_listings: usize,
+    pub unfilled_listings: usize,
+    // Trades the company took part in which moved the price beyond the spike threshold
+    pub price_spikes: usize,
+}
+
+impl CompanyManipulationReport {
+    pub fn get_unfilled_ratio(&self) -> f64 {
+        if self.placed_listings == 0 {
+            return 0.0;
+        }
+        self.unfilled_listings as f64 / self.placed_listings as f64
+    }
+
+    pub fn get_flags(&self, thresholds: &ManipulationThresholds) -> Vec<&'static str> {
+        let mut flags: Vec<&'static str> = vec![];
+        if self.self_trades > 0 {
+            flags.push("wash trading");
+        }
+        if !self.dominant_resources.is_empty() {
+            flags.push("cornering");
+        }
+        if self.placed_listings >= thresholds.min_listings
+            && self.get_unfilled_ratio() >= thresholds.unfilled_ratio
+        {
+            flags.push("spoofing");
+        }
+        if self.price_spikes > 0 {
+            flags.push("price spikes");
+        }
+        flags
+    }
+}
+
+pub struct ManipulationReport {
+    pub companies: Vec<CompanyManipulationReport>,
+    pub thresholds: ManipulationThresholds,
+}
+
+impl ManipulationReport {
+    pub fn new(company_count: usize, thresholds: ManipulationThresholds) -> ManipulationReport {
+        ManipulationReport {
+            companies: vec![CompanyManipulationReport::default(); company_count],
+            thresholds,
+        }
+    }
+
+    // Analyzes the markets of all regions
+    pub fn from_world(world: &World, thresholds: ManipulationThresholds) -> ManipulationReport {
+        let mut report = ManipulationReport::new(world.company_data.companies.len(), thresholds);
+        report.analyze(&world.market_data.log);
+        for region in world.region_data.regions.iter() {
+            report.analyze(&region.market_data.log);
+        }
+        report
+    }
+
+    pub fn analyze(&mut self, market_log: &MarketLog) {
+        self.find_self_trades(market_log);
+        self.find_dominant_shares(market_log);
+        self.find_unfilled_listings(market_log);
+        self.find_price_spikes(market_log);
+    }
+
+    fn find_self_trades(&mut self, market_log: &MarketLog) {
+        for record in market_log.trades.iter() {
+            match (record.trade.buyer, record.trade.seller) {
+                (Some(buyer), Some(seller)) if buyer == seller => {
+                    self.companies[buyer].self_trades += 1;
+                    self.companies[buyer].self_trade_volume += record.trade.get_volume();
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn find_dominant_shares(&mut self, market_log: &MarketLog) {
+        let mut traded_amounts: HashMap<ResourceHandle, f64> = HashMap::new();
+        let mut company_amounts: HashMap<(ResourceHandle, CompanyHandle, bool), f64> =
+            HashMap::new();
+        for record in market_log.trades.iter() {
+            let trade = &record.trade;
+            *traded_amounts.entry(trade.resource).or_insert(0.0) += trade.amount;
+            if let Some(buyer) = trade.buyer {
+                *company_amounts
+                    .entry((trade.resource, buyer, true))
+                    .or_insert(0.0) += trade.amount;
+            }
+            if let Some(seller) = trade.seller {
+                *company_amounts
+                    .entry((trade.resource, seller, false))
+                    .or_insert(0.0) += trade.amount;
+            }
+        }
+        let mut shares: HashMap<(ResourceHandle, CompanyHandle), f64> = HashMap::new();
+        for ((resource, company, _), amount) in company_amounts.iter() {
+            let traded_amount = traded_amounts[resource];
+            if traded_amount < self.thresholds.min_traded_amount {
+                continue;
+            }
+            let share = shares.entry((*resource, *company)).or_insert(0.0);
+            *share = share.max(amount / traded_amount);
+        }
+        let mut dominant_shares: Vec<(&(ResourceHandle, CompanyHandle), &f64)> = shares
+            .iter()
+            .filter(|(_, share)| **share >= self.thresholds.dominant_share)
+            .collect();
+        dominant_shares.sort_by_key(|((resource, company), _)| (*resource, *company));
+        for ((resource, company), share) in dominant_shares {
+            self.companies[*company]
+                .dominant_resources
+                .push((*resource, *share));
+        }
+    }
+
+    fn find_unfilled_listings(&mut self, market_log: &MarketLog) {
+        let mut placed_amounts: HashMap<(ListingSide, usize), f64> = HashMap::new();
+        for event in market_log.listing_events.iter() {
+            let company = match event.company {
+                Some(company) => company,
+                None => continue,
+            };
+            match event.kind {
+                ListingEventKind::Placed => {
+                    self.companies[company].placed_listings += 1;
+                    placed_amounts.insert((event.side, event.handle), event.amount);
+                }
+                ListingEventKind::Cancelled | ListingEventKind::Expired => {
+                    if let Some(placed_amount) = placed_amounts.get(&(event.side, event.handle)) {
+                        if event.amount >= *placed_amount {
+                            self.companies[company].unfilled_listings += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn find_price_spikes(&mut self, market_log: &MarketLog) {
+        let mut last_prices: HashMap<ResourceHandle, f64> = HashMap::new();
+        for record in market_log.trades.iter() {
+            let trade = &record.trade;
+            if let Some(last_price) = last_prices.insert(trade.resource, trade.price_per_unit) {
+                if last_price <= 0.0
+                    || (trade.price_per_unit / last_price - 1.0).abs()
+                        <= self.thresholds.price_spike
+                {
+                    continue;
+                }
+                if let Some(buyer) = trade.buyer {
+                    self.companies[buyer].price_spikes += 1;
+                }
+                if let Some(seller) = trade.seller.filter(|seller| Some(*seller) != trade.buyer) {
+                    self.companies[seller].price_spikes += 1;
+                }
+            }
+        }
+    }
+
+    pub fn print(&self, company_data: &CompanyData, resource_data: &ResourceData) {
+        info!("Market manipulation report:");
+        for (company_handle, report) in self.companies.iter().enumerate() {
+            let company_name = company_data
+                .get_company_name_by_handle(company_handle)
+                .unwrap_or_default();
+            info!(
+                " - {}: {} self trades ({} volume), {}/{} listings unfilled, {} price spikes",
+                company_name,
+                report.self_trades,
+                report.self_trade_volume,
+                report.unfilled_listings,
+                report.placed_listings,
+                report.price_spikes
+            );
+            for (resource, share) in report.dominant_resources.iter() {
+                info!(
+                    "   dominates {} with {:.0}% of the traded amount",
+                    resource_data
+                        .get_resource_name_by_handle(*resource)
+                        .unwrap_or_default(),
+                    share * 100.0
+                );
+            }
+            let flags = report.get_flags(&self.thresholds);
+            if !flags.is_empty() {
+                info!("   flagged for {}", flags.join(", "));
+            }
+        }
+    }
+}
@@ -0,0 +1,6 @@
+pub mod evaluation;
+pub mod manipulation;
+pub mod production_graph;
+pub mod production_plan;
+pub mod profitability;
+pub mod simplex;
@@ -0,0 +1,342 @@
+use crate::economy::recipe::RecipeHandle;
+use crate::economy::resource::ResourceHandle;
+use crate::world::World;
+use crate::world_data::recipe_data::RecipeData;
+use crate::world_data::resource_data::ResourceData;
+use log::{info, warn};
+use std::collections::HashSet;
+
+// Bipartite graph of resources and the recipes turning ingredients into products
+pub struct ProductionGraph<'a> {
+    pub recipe_data: &'a RecipeData,
+    pub resource_data: &'a ResourceData,
+    // Resources entering the economy from outside, e.g. from producers and households
+    pub sources: HashSet<ResourceHandle>,
+    // Resources leaving the economy, e.g. bought by consumers
+    pub sinks: HashSet<ResourceHandle>,
+}
+
+impl<'a> ProductionGraph<'a> {
+    pub fn new(recipe_data: &'a RecipeData, resource_data: &'a ResourceData) -> Self {
+        ProductionGraph {
+            recipe_data,
+            resource_data,
+            sources: HashSet::new(),
+            sinks: HashSet::new(),
+        }
+    }
+
+    pub fn from_world(world: &'a World) -> Self {
+        let mut graph = ProductionGraph::new(&world.recipe_data, &world.resource_data);
+        for producer in world.producer_data.producers.iter() {
+            graph
+                .sources
+                .extend(producer.production.iter().map(|offer| offer.resource));
+        }
+        if let Some(labor_resource) = world.labor_data.labor_resource {
+            graph.sources.insert(labor_resource);
+        }
+        for consumer in world.consumer_data.consumers.iter() {
+            graph
+                .sinks
+                .extend(consumer.consumption.iter().map(|order| order.resource));
+        }
+        graph
+    }
+
+    fn get_resource_count(&self) -> usize {
+        self.resource_data.resources.len()
+    }
+
+    fn get_resource_name(&self, resource: ResourceHandle) -> &str {
+        self.resource_data
+            .get_resource_name_by_handle(resource)
+            .unwrap_or_default()
+    }
+
+    // Resources which a recipe turns the resource into
+    fn get_successors(&self, resource: ResourceHandle) -> Vec<ResourceHandle> {
+        let mut successors: Vec<ResourceHandle> = self
+            .recipe_data
+            .recipes
+            .iter()
+            .filter(|recipe| recipe.ingredients.contains_key(&resource))
+            .flat_map(|recipe| recipe.products.keys().copied())
+            .collect();
+        successors.sort();
+        successors.dedup();
+        successors
+    }
+
+    fn get_sorted<T: Copy + Ord>(values: impl Iterator<Item = (T, f64)>) -> Vec<(T, f64)> {
+        let mut values: Vec<(T, f64)> = values.collect();
+        values.sort_by_key(|(key, _)| *key);
+        values
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph production {\n    rankdir=LR;\n");
+        for (resource, resource_entry) in self.resource_data.resources.iter().enumerate() {
+            let shape = if self.sources.contains(&resource) {
+                "house"
+            } else if self.sinks.contains(&resource) {
+                "invhouse"
+            } else {
+                "ellipse"
+            };
+            dot += &format!(
+                "    r{} [label=\"{}\", shape={}];\n",
+                resource, resource_entry.name, shape
+            );
+        }
+        for (recipe_handle, recipe) in self.recipe_data.recipes.iter().enumerate() {
+            dot += &format!(
+                "    p{} [label=\"{}\", shape=box];\n",
+                recipe_handle, recipe.name
+            );
+            for (resource, amount) in
+                ProductionGraph::get_sorted(recipe.ingredients.iter().map(|(r, a)| (*r, *a)))
+            {
+                dot += &format!(
+                    "    r{} -> p{} [label=\"{}\"];\n",
+                    resource, recipe_handle, amount
+                );
+            }
+            for (resource, amount) in
+                ProductionGraph::get_sorted(recipe.products.iter().map(|(r, a)| (*r, *a)))
+            {
+                dot += &format!(
+                    "    p{} -> r{} [label=\"{}\"];\n",
+                    recipe_handle, resource, amount
+                );
+            }
+        }
+        dot += "}\n";
+        dot
+    }
+
+    pub fn to_mermaid(&self) -> String {
+        let mut mermaid = String::from("flowchart LR\n");
+        for (resource, resource_entry) in self.resource_data.resources.iter().enumerate() {
+            mermaid += &format!("    r{}([\"{}\"])\n", resource, resource_entry.name);
+        }
+        for (recipe_handle, recipe) in self.recipe_data.recipes.iter().enumerate() {
+            mermaid += &format!("    p{}[\"{}\"]\n", recipe_handle, recipe.name);
+            for (resource, amount) in
+                ProductionGraph::get_sorted(recipe.ingredients.iter().map(|(r, a)| (*r, *a)))
+            {
+                mermaid += &format!("    r{} -->|{}| p{}\n", resource, amount, recipe_handle);
+            }
+            for (resource, amount) in
+                ProductionGraph::get_sorted(recipe.products.iter().map(|(r, a)| (*r, *a)))
+            {
+                mermaid += &format!("    p{} -->|{}| r{}\n", recipe_handle, amount, resource);
+            }
+        }
+        mermaid
+    }
+
+    // Resources obtainable from the sources, a recipe needs all of its ingredients
+    pub fn get_reachable_resources(&self) -> HashSet<ResourceHandle> {
+        let mut reachable = self.sources.clone();
+        loop {
+            let mut changed = false;
+            for recipe in self.recipe_data.recipes.iter() {
+                if recipe
+                    .ingredients
+                    .keys()
+                    .all(|resource| reachable.contains(resource))
+                {
+                    for product in recipe.products.keys() {
+                        changed |= reachable.insert(*product);
+                    }
+                }
+            }
+            if !changed {
+                return reachable;
+            }
+        }
+    }
+
+    pub fn get_unreachable_resources(&self) -> Vec<ResourceHandle> {
+        let reachable = self.get_reachable_resources();
+        (0..self.get_resource_count())
+            .filter(|resource| !reachable.contains(resource))
+            .collect()
+    }
+
+    pub fn get_unproduced_resources(&self) -> Vec<ResourceHandle> {
+        (0..self.get_resource_count())
+            .filter(|resource| {
+                !self.sources.contains(resource)
+                    && !self
+                        .recipe_data
+                        .recipes
+                        .iter()
+                        .any(|recipe| recipe.products.contains_key(resource))
+            })
+            .collect()
+    }
+
+    pub fn get_unconsumed_resources(&self) -> Vec<ResourceHandle> {
+        (0..self.get_resource_count())
+            .filter(|resource| {
+                !self.sinks.contains(resource)
+                    && !self
+                        .recipe_data
+                        .recipes
+                        .iter()
+                        .any(|recipe| recipe.ingredients.contains_key(resource))
+            })
+            .collect()
+    }
+
+    // Strongly connected components of the resource graph which contain a cycle
+    pub fn get_cycles(&self) -> Vec<Vec<ResourceHandle>> {
+        let resource_count = self.get_resource_count();
+        let successors: Vec<Vec<ResourceHandle>> = (0..resource_count)
+            .map(|resource| self.get_successors(resource))
+            .collect();
+        // Tarjan's algorithm
+        let mut index = 0;
+        let mut indices: Vec<Option<usize>> = vec![None; resource_count];
+        let mut low_links: Vec<usize> = vec![0; resource_count];
+        let mut on_stack: Vec<bool> = vec![false; resource_count];
+        let mut stack: Vec<ResourceHandle> = vec![];
+        let mut cycles: Vec<Vec<ResourceHandle>> = vec![];
+        #[allow(clippy::too_many_arguments)]
+        fn connect(
+            resource: ResourceHandle,
+            successors: &[Vec<ResourceHandle>],
+            index: &mut usize,
+            indices: &mut [Option<usize>],
+            low_links: &mut [usize],
+            on_stack: &mut [bool],
+            stack: &mut Vec<ResourceHandle>,
+            cycles: &mut Vec<Vec<ResourceHandle>>,
+        ) {
+            indices[resource] = Some(*index);
+            low_links[resource] = *index;
+            *index += 1;
+            stack.push(resource);
+            on_stack[resource] = true;
+            for successor in successors[resource].iter().copied() {
+                match indices[successor] {
+                    None => {
+                        connect(
+                            successor, successors, index, indices, low_links, on_stack, stack,
+                            cycles,
+                        );
+                        low_links[resource] = low_links[resource].min(low_links[successor]);
+                    }
+                    Some(successor_index) if on_stack[successor] => {
+                        low_links[resource] = low_links[resource].min(successor_index);
+                    }
+                    _ => {}
+                }
+            }
+            if Some(low_links[resource]) == indices[resource] {
+                let mut component: Vec<ResourceHandle> = vec![];
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == resource {
+                        break;
+                    }
+                }
+                if component.len() > 1 || successors[resource].contains(&resource) {
+                    component.sort();
+                    cycles.push(component);
+                }
+            }
+        }
+        for resource in 0..resource_count {
+            if indices[resource].is_none() {
+                connect(
+                    resource,
+                    &successors,
+                    &mut index,
+                    &mut indices,
+                    &mut low_links,
+                    &mut on_stack,
+                    &mut stack,
+                    &mut cycles,
+                );
+            }
+        }
+        cycles.sort();
+        cycles
+    }
+
+    pub fn get_misnamed_recipes(&self) -> Vec<RecipeHandle> {
+        self.recipe_data
+            .recipes
+            .iter()
+            .enumerate()
+            .filter(|(_, recipe)| {
+                !recipe
+                    .products
+                    .keys()
+                    .any(|resource| self.get_resource_name(*resource) == recipe.name)
+            })
+            .map(|(recipe_handle, _)| recipe_handle)
+            .collect()
+    }
+
+    fn get_names(&self, resources: &[ResourceHandle]) -> String {
+        resources
+            .iter()
+            .map(|resource| self.get_resource_name(*resource))
+            .collect::<Vec<&str>>()
+            .join(", ")
+    }
+
+    // Logs all findings and returns the number of issues
+    pub fn print_checks(&self) -> usize {
+        let mut issues = 0;
+        info!("Production graph checks:");
+        let unreachable_resources = self.get_unreachable_resources();
+        if !unreachable_resources.is_empty() {
+            issues += unreachable_resources.len();
+            warn!(
+                " - unreachable resources: {}",
+                self.get_names(&unreachable_resources)
+            );
+        }
+        let unproduced_resources = self.get_unproduced_resources();
+        if !unproduced_resources.is_empty() {
+            issues += unproduced_resources.len();
+            warn!(
+                " - resources never produced: {}",
+                self.get_names(&unproduced_resources)
+            );
+        }
+        let unconsumed_resources = self.get_unconsumed_resources();
+        if !unconsumed_resources.is_empty() {
+            issues += unconsumed_resources.len();
+            warn!(
+                " - resources never consumed: {}",
+                self.get_names(&unconsumed_resources)
+            );
+        }
+        for cycle in self.get_cycles() {
+            issues += 1;
+            warn!(" - cycle between {}", self.get_names(&cycle));
+        }
+        for recipe_handle in self.get_misnamed_recipes() {
+            issues += 1;
+            let recipe = &self.recipe_data.recipes[recipe_handle];
+            let mut products: Vec<ResourceHandle> = recipe.products.keys().copied().collect();
+            products.sort();
+            warn!(
+                " - recipe {} produces {} only",
+                recipe.name,
+                self.get_names(&products)
+            );
+        }
+        if issues == 0 {
+            info!(" - no issues found");
+        }
+        issues
+    }
+}
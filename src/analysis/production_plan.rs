@@ -0,0 +1,251 @@
+use crate::analysis::simplex::{LinearProgram, LinearProgramSolution};
+use crate::economy::recipe::RecipeHandle;
+use crate::economy::resource::ResourceHandle;
+use crate::world::World;
+use crate::world_data::company_data::CompanyData;
+use crate::world_data::processor_data::ProcessorData;
+use crate::world_data::recipe_data::RecipeData;
+use crate::world_data::resource_data::ResourceData;
+use log::{info, warn};
+
+// Steady-state amount of a resource per tick at a fixed price per unit
+#[derive(Clone)]
+pub struct Flow {
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price: f64,
+}
+
+pub struct ProductionPlan {
+    // Number of processors running each recipe every tick, fractional processors are allowed
+    pub processors: Vec<f64>,
+    pub purchases: Vec<Flow>,
+    pub sales: Vec<Flow>,
+    // Revenue minus purchase costs and amortized processor costs per tick
+    pub profit: f64,
+}
+
+// Profit-maximizing steady state of the whole economy, regions and transport are ignored
+pub struct ProductionPlanner<'a> {
+    pub recipe_data: &'a RecipeData,
+    pub processor_data: &'a ProcessorData,
+    // Raw resources and labor per tick, the amount is the available maximum
+    pub supplies: Vec<Flow>,
+    // Consumer demand per tick, the amount is the maximum bought at the price
+    pub demands: Vec<Flow>,
+    // Ticks over which a processor's price is spread
+    pub amortization_ticks: f64,
+}
+
+impl<'a> ProductionPlanner<'a> {
+    pub fn new(
+        recipe_data: &'a RecipeData,
+        processor_data: &'a ProcessorData,
+        amortization_ticks: f64,
+    ) -> Self {
+        ProductionPlanner {
+            recipe_data,
+            processor_data,
+            supplies: vec![],
+            demands: vec![],
+            amortization_ticks,
+        }
+    }
+
+    pub fn from_world(world: &'a World, amortization_ticks: f64) -> Self {
+        let mut planner = ProductionPlanner::new(
+            &world.recipe_data,
+            &world.processor_data,
+            amortization_ticks,
+        );
+        for producer in world.producer_data.producers.iter() {
+            let ticks = producer.offer_creation_ticks.max(1) as f64;
+            planner
+                .supplies
+                .extend(producer.production.iter().map(|offer| Flow {
+                    resource: offer.resource,
+                    amount: offer.amount / ticks,
+                    price: offer.price_per_unit,
+                }));
+        }
+        if let Some(labor_resource) = world.labor_data.labor_resource {
+            planner
+                .supplies
+                .extend(world.labor_data.households.iter().map(|household| Flow {
+                    resource: labor_resource,
+                    amount: household.workers * world.labor_data.hours_per_worker,
+                    price: household.reservation_wage,
+                }));
+        }
+        for consumer in world.consumer_data.consumers.iter() {
+            let ticks = consumer.order_creation_ticks.max(1) as f64;
+            planner
+                .demands
+                .extend(consumer.consumption.iter().map(|order| Flow {
+                    resource: order.resource,
+                    amount: order.amount / ticks,
+                    price: order.max_price_per_unit,
+                }));
+        }
+        planner
+    }
+
+    fn get_processor_cost(&self, recipe: RecipeHandle) -> f64 {
+        self.processor_data.get_processor_price(recipe) / self.amortization_ticks.max(1.0)
+    }
+
+    // Variables are the processors per recipe, then the supplies, then the demands. Processor
+    // limits restrict the number of processors per recipe. Returns None if the profit is
+    // unbounded, e.g. if a recipe creates resources from nothing.
+    pub fn solve(&self, processor_limits: Option<&[f64]>) -> Option<ProductionPlan> {
+        let recipe_count = self.recipe_data.recipes.len();
+        let supply_offset = recipe_count;
+        let demand_offset = supply_offset + self.supplies.len();
+        let variable_count = demand_offset + self.demands.len();
+        let mut objective: Vec<f64> = (0..recipe_count)
+            .map(|recipe| -self.get_processor_cost(recipe))
+            .collect();
+        objective.extend(self.supplies.iter().map(|supply| -supply.price));
+        objective.extend(self.demands.iter().map(|demand| demand.price));
+        let mut program = LinearProgram::new(objective);
+        // A resource can't be used or sold in larger amounts than it is bought or produced
+        let mut resources: Vec<ResourceHandle> = self
+            .recipe_data
+            .recipes
+            .iter()
+            .flat_map(|recipe| recipe.ingredients.keys().chain(recipe.products.keys()))
+            .chain(self.supplies.iter().map(|supply| &supply.resource))
+            .chain(self.demands.iter().map(|demand| &demand.resource))
+            .copied()
+            .collect();
+        resources.sort();
+        resources.dedup();
+        for resource in resources {
+            let mut coefficients = vec![0.0; variable_count];
+            for (recipe_handle, recipe) in self.recipe_data.recipes.iter().enumerate() {
+                coefficients[recipe_handle] = recipe.ingredients.get(&resource).unwrap_or(&0.0)
+                    - recipe.products.get(&resource).unwrap_or(&0.0);
+            }
+            for (i, supply) in self.supplies.iter().enumerate() {
+                if supply.resource == resource {
+                    coefficients[supply_offset + i] = -1.0;
+                }
+            }
+            for (i, demand) in self.demands.iter().enumerate() {
+                if demand.resource == resource {
+                    coefficients[demand_offset + i] = 1.0;
+                }
+            }
+            program.add_constraint(coefficients, 0.0);
+        }
+        for (i, supply) in self.supplies.iter().enumerate() {
+            program.add_upper_bound(supply_offset + i, supply.amount);
+        }
+        for (i, demand) in self.demands.iter().enumerate() {
+            program.add_upper_bound(demand_offset + i, demand.amount);
+        }
+        if let Some(processor_limits) = processor_limits {
+            for (recipe, limit) in processor_limits.iter().enumerate().take(recipe_count) {
+                program.add_upper_bound(recipe, *limit);
+            }
+        }
+        match program.maximize() {
+            LinearProgramSolution::Optimal { values, objective } => {
+                let get_flows = |flows: &[Flow], offset: usize| -> Vec<Flow> {
+                    flows
+                        .iter()
+                        .enumerate()
+                        .filter(|(i, _)| values[offset + i] > 0.0)
+                        .map(|(i, flow)| Flow {
+                            amount: values[offset + i],
+                            ..flow.clone()
+                        })
+                        .collect()
+                };
+                Some(ProductionPlan {
+                    processors: values[..recipe_count].to_vec(),
+                    purchases: get_flows(&self.supplies, supply_offset),
+                    sales: get_flows(&self.demands, demand_offset),
+                    profit: objective,
+                })
+            }
+            LinearProgramSolution::Unbounded | LinearProgramSolution::Infeasible => None,
+        }
+    }
+}
+
+// Number of processors per recipe owned by all companies
+pub fn get_processor_allocation(company_data: &CompanyData, recipe_count: usize) -> Vec<f64> {
+    let mut allocation = vec![0.0; recipe_count];
+    for company in company_data.companies.iter() {
+        for processor in company.processors.iter() {
+            if let Some(count) = allocation.get_mut(processor.recipe) {
+                *count += 1.0;
+            }
+        }
+    }
+    allocation
+}
+
+fn print_flows(title: &str, flows: &[Flow], resource_data: &ResourceData) {
+    if flows.is_empty() {
+        return;
+    }
+    let flows: Vec<String> = flows
+        .iter()
+        .map(|flow| {
+            format!(
+                "{} {:.3} at {:.2}",
+                resource_data
+                    .get_resource_name_by_handle(flow.resource)
+                    .unwrap_or_default(),
+                flow.amount,
+                flow.price
+            )
+        })
+        .collect();
+    info!("   {}: {}", title, flows.join(", "));
+}
+
+// Compares the optimal plan to the actual allocation and to the best plan the actual allocation
+// allows
+pub fn print_plan_comparison(
+    planner: &ProductionPlanner,
+    actual_allocation: &[f64],
+    resource_data: &ResourceData,
+) {
+    let optimal_plan = match planner.solve(None) {
+        Some(plan) => plan,
+        None => {
+            warn!("Production plan is unbounded, a recipe creates resources from nothing");
+            return;
+        }
+    };
+    let actual_plan = planner.solve(Some(actual_allocation));
+    info!(
+        "Production plan per tick, processors amortized over {} ticks:",
+        planner.amortization_ticks
+    );
+    for (recipe_handle, recipe) in planner.recipe_data.recipes.iter().enumerate() {
+        info!(
+            " - {}: optimal {:.2} processors, actual {} processors, {:.2} of them used at best",
+            recipe.name,
+            optimal_plan.processors[recipe_handle],
+            actual_allocation[recipe_handle],
+            actual_plan
+                .as_ref()
+                .map(|plan| plan.processors[recipe_handle])
+                .unwrap_or(0.0)
+        );
+    }
+    info!(" - optimal profit {:.2}", optimal_plan.profit);
+    print_flows("purchases", &optimal_plan.purchases, resource_data);
+    print_flows("sales", &optimal_plan.sales, resource_data);
+    if let Some(actual_plan) = actual_plan {
+        info!(
+            " - best profit of the actual allocation {:.2} ({:.2} below optimal)",
+            actual_plan.profit,
+            optimal_plan.profit - actual_plan.profit
+        );
+    }
+}
@@ -0,0 +1,231 @@
+use crate::economy::recipe::{Recipe, RecipeHandle};
+use crate::economy::resource::ResourceHandle;
+use crate::world::World;
+use crate::world_data::processor_data::ProcessorData;
+use crate::world_data::recipe_data::RecipeData;
+use crate::world_data::resource_data::ResourceData;
+use log::info;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PriceSource {
+    // Cheapest open offer of the home market
+    BestAsk,
+    // Highest open order of the home market
+    BestBid,
+    // Price of the last trade in the home market
+    LastPrice,
+    // Cheapest price producers sell at
+    Producer,
+}
+
+pub struct PriceVector {
+    pub prices: Vec<Option<f64>>,
+}
+
+impl PriceVector {
+    // Takes each price from the first source which has one, labor without a price is valued at
+    // the households' average wage
+    pub fn from_sources(world: &World, sources: &[PriceSource]) -> PriceVector {
+        let resource_count = world.resource_data.resources.len();
+        let market_data = &world.market_data;
+        let prices = (0..resource_count)
+            .map(|resource| {
+                sources
+                    .iter()
+                    .find_map(|source| match source {
+                        PriceSource::BestAsk => market_data
+                            .price_index
+                            .get(&resource)
+                            .cloned()
+                            .flatten()
+                            .map(|(_, price)| price),
+                        PriceSource::BestBid => market_data
+                            .order_index
+                            .get(&resource)
+                            .cloned()
+                            .flatten()
+                            .map(|(_, price)| price),
+                        PriceSource::LastPrice => market_data.get_last_price(resource),
+                        PriceSource::Producer => world
+                            .producer_data
+                            .producers
+                            .iter()
+                            .flat_map(|producer| producer.production.iter())
+                            .filter(|offer| offer.resource == resource)
+                            .map(|offer| offer.price_per_unit)
+                            .min_by(|a, b| a.total_cmp(b)),
+                    })
+                    .or_else(|| {
+                        let average_wage = world.labor_data.get_average_wage();
+                        match world.labor_data.labor_resource == Some(resource)
+                            && average_wage > 0.0
+                        {
+                            true => Some(average_wage),
+                            false => None,
+                        }
+                    })
+            })
+            .collect();
+        PriceVector { prices }
+    }
+
+    pub fn get_price(&self, resource: ResourceHandle) -> Option<f64> {
+        self.prices.get(resource).copied().flatten()
+    }
+}
+
+pub struct RecipeProfitability {
+    pub recipe: RecipeHandle,
+    // Costs and value per processor tick of a new processor
+    pub input_cost: f64,
+    pub output_value: f64,
+    pub margin: f64,
+    pub processor_price: f64,
+    // Processor ticks until the processor has paid for itself
+    pub payback_ticks: Option<f64>,
+    // Highest price per unit of each ingredient at which the recipe still breaks even, empty if
+    // prices are missing
+    pub break_even_prices: Vec<(ResourceHandle, f64)>,
+    // Resources without a price, they are valued at zero
+    pub missing_prices: Vec<ResourceHandle>,
+}
+
+impl RecipeProfitability {
+    pub fn new(
+        recipe_handle: RecipeHandle,
+        recipe: &Recipe,
+        processor_data: &ProcessorData,
+        prices: &PriceVector,
+    ) -> RecipeProfitability {
+        let mut missing_prices: Vec<ResourceHandle> = vec![];
+        let mut get_value =
+            |(resource, amount): (&ResourceHandle, &f64)| match prices.get_price(*resource) {
+                Some(price) => price * amount,
+                None => {
+                    missing_prices.push(*resource);
+                    0.0
+                }
+            };
+        let input_cost = recipe
+            .ingredients
+            .iter()
+            .map(&mut get_value)
+            .fold(0.0, |a, b| a + b);
+        let output_value = recipe
+            .products
+            .iter()
+            .map(&mut get_value)
+            .fold(0.0, |a, b| a + b);
+        missing_prices.sort();
+        missing_prices.dedup();
+        let margin = output_value - input_cost;
+        let processor_price = processor_data.get_processor_price(recipe_handle);
+        let mut break_even_prices: Vec<(ResourceHandle, f64)> = match missing_prices.is_empty() {
+            true => recipe
+                .ingredients
+                .iter()
+                .map(|(resource, amount)| {
+                    let price = prices.get_price(*resource).unwrap_or(0.0);
+                    (*resource, price + margin / amount)
+                })
+                .collect(),
+            false => vec![],
+        };
+        break_even_prices.sort_by_key(|(resource, _)| *resource);
+        RecipeProfitability {
+            recipe: recipe_handle,
+            input_cost,
+            output_value,
+            margin,
+            processor_price,
+            payback_ticks: if margin > 0.0 {
+                Some(processor_price / margin)
+            } else {
+                None
+            },
+            break_even_prices,
+            missing_prices,
+        }
+    }
+
+    pub fn get_margin_rate(&self) -> f64 {
+        if self.input_cost > 0.0 {
+            self.margin / self.input_cost
+        } else {
+            0.0
+        }
+    }
+}
+
+// Profitability of all recipes, most profitable first
+pub fn analyze_recipes(
+    recipe_data: &RecipeData,
+    processor_data: &ProcessorData,
+    prices: &PriceVector,
+) -> Vec<RecipeProfitability> {
+    let mut profitabilities: Vec<RecipeProfitability> = recipe_data
+        .recipes
+        .iter()
+        .enumerate()
+        .map(|(recipe_handle, recipe)| {
+            RecipeProfitability::new(recipe_handle, recipe, processor_data, prices)
+        })
+        .collect();
+    profitabilities.sort_by(|a, b| b.margin.total_cmp(&a.margin));
+    profitabilities
+}
+
+pub fn print_profitability_report(
+    profitabilities: &[RecipeProfitability],
+    recipe_data: &RecipeData,
+    resource_data: &ResourceData,
+) {
+    let get_resource_name = |resource: ResourceHandle| {
+        resource_data
+            .get_resource_name_by_handle(resource)
+            .unwrap_or_default()
+            .to_string()
+    };
+    info!("Recipe profitability per processor tick:");
+    for profitability in profitabilities.iter() {
+        let recipe_name = recipe_data
+            .get_recipe_by_handle(profitability.recipe)
+            .map(|recipe| recipe.name.as_str())
+            .unwrap_or_default();
+        info!(
+            " - {}: input cost {:.2}, output value {:.2}, margin {:.2} ({:.1}%), payback {}",
+            recipe_name,
+            profitability.input_cost,
+            profitability.output_value,
+            profitability.margin,
+            profitability.get_margin_rate() * 100.0,
+            match profitability.payback_ticks {
+                Some(ticks) => format!(
+                    "after {:.0} ticks for {:.2}",
+                    ticks, profitability.processor_price
+                ),
+                None => String::from("never"),
+            }
+        );
+        let break_even_prices: Vec<String> = profitability
+            .break_even_prices
+            .iter()
+            .map(|(resource, price)| format!("{} {:.2}", get_resource_name(*resource), price))
+            .collect();
+        if !break_even_prices.is_empty() {
+            info!(
+                "   break-even ingredient prices: {}",
+                break_even_prices.join(", ")
+            );
+        }
+        if !profitability.missing_prices.is_empty() {
+            let missing_prices: Vec<String> = profitability
+                .missing_prices
+                .iter()
+                .map(|resource| get_resource_name(*resource))
+                .collect();
+            info!("   no prices for {}", missing_prices.join(", "));
+        }
+    }
+}
@@ -0,0 +1,165 @@
+// Tolerance below which tableau entries are treated as zero
+const EPSILON: f64 = 1e-9;
+
+pub enum LinearProgramSolution {
+    Optimal { values: Vec<f64>, objective: f64 },
+    Unbounded,
+    // Only reported for negative bounds, the solver needs the origin to be feasible
+    Infeasible,
+}
+
+// Maximizes objective * x subject to coefficients * x <= bound for every constraint and x >= 0
+pub struct LinearProgram {
+    pub objective: Vec<f64>,
+    pub constraints: Vec<(Vec<f64>, f64)>,
+}
+
+impl LinearProgram {
+    pub fn new(objective: Vec<f64>) -> LinearProgram {
+        LinearProgram {
+            objective,
+            constraints: vec![],
+        }
+    }
+
+    pub fn get_variable_count(&self) -> usize {
+        self.objective.len()
+    }
+
+    pub fn add_constraint(&mut self, coefficients: Vec<f64>, bound: f64) {
+        self.constraints.push((coefficients, bound));
+    }
+
+    pub fn add_upper_bound(&mut self, variable: usize, bound: f64) {
+        let mut coefficients = vec![0.0; self.get_variable_count()];
+        coefficients[variable] = 1.0;
+        self.add_constraint(coefficients, bound);
+    }
+
+    // Primal simplex on a dense tableau with slack variables as the initial basis, Bland's rule
+    // prevents cycling
+    pub fn maximize(&self) -> LinearProgramSolution {
+        if self.constraints.iter().any(|(_, bound)| *bound < 0.0) {
+            return LinearProgramSolution::Infeasible;
+        }
+        let variable_count = self.get_variable_count();
+        let row_count = self.constraints.len();
+        let column_count = variable_count + row_count + 1;
+        let rhs = column_count - 1;
+        let mut tableau: Vec<Vec<f64>> = self
+            .constraints
+            .iter()
+            .enumerate()
+            .map(|(row, (coefficients, bound))| {
+                let mut tableau_row = vec![0.0; column_count];
+                tableau_row[..coefficients.len()].copy_from_slice(coefficients);
+                tableau_row[variable_count + row] = 1.0;
+                tableau_row[rhs] = *bound;
+                tableau_row
+            })
+            .collect();
+        let mut reduced_costs = vec![0.0; column_count];
+        for (column, coefficient) in self.objective.iter().enumerate() {
+            reduced_costs[column] = -coefficient;
+        }
+        let mut basis: Vec<usize> = (variable_count..variable_count + row_count).collect();
+        while let Some(entering) = (0..rhs).find(|column| reduced_costs[*column] < -EPSILON) {
+            let leaving = (0..row_count)
+                .filter(|row| tableau[*row][entering] > EPSILON)
+                .min_by(|a, b| {
+                    let ratio_a = tableau[*a][rhs] / tableau[*a][entering];
+                    let ratio_b = tableau[*b][rhs] / tableau[*b][entering];
+                    ratio_a.total_cmp(&ratio_b).then(basis[*a].cmp(&basis[*b]))
+                });
+            let leaving = match leaving {
+                Some(leaving) => leaving,
+                None => return LinearProgramSolution::Unbounded,
+            };
+            let pivot = tableau[leaving][entering];
+            for value in tableau[leaving].iter_mut() {
+                *value /= pivot;
+            }
+            let pivot_row = tableau[leaving].clone();
+            for (row, tableau_row) in tableau.iter_mut().enumerate() {
+                let factor = tableau_row[entering];
+                if row != leaving && factor != 0.0 {
+                    for (value, pivot_value) in tableau_row.iter_mut().zip(pivot_row.iter()) {
+                        *value -= factor * pivot_value;
+                    }
+                }
+            }
+            let factor = reduced_costs[entering];
+            for (value, pivot_value) in reduced_costs.iter_mut().zip(pivot_row.iter()) {
+                *value -= factor * pivot_value;
+            }
+            basis[leaving] = entering;
+        }
+        let mut values = vec![0.0; variable_count];
+        for (row, variable) in basis.iter().enumerate() {
+            if *variable < variable_count {
+                values[*variable] = tableau[row][rhs].max(0.0);
+            }
+        }
+        LinearProgramSolution::Optimal {
+            values,
+            objective: reduced_costs[rhs],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_optimum(program: &LinearProgram, expected_values: &[f64], expected_objective: f64) {
+        match program.maximize() {
+            LinearProgramSolution::Optimal { values, objective } => {
+                assert!((objective - expected_objective).abs() < 1e-6);
+                for (value, expected_value) in values.iter().zip(expected_values.iter()) {
+                    assert!((value - expected_value).abs() < 1e-6);
+                }
+            }
+            _ => panic!("expected an optimal solution"),
+        }
+    }
+
+    #[test]
+    fn finds_known_optimum() {
+        let mut program = LinearProgram::new(vec![3.0, 5.0]);
+        program.add_upper_bound(0, 4.0);
+        program.add_constraint(vec![0.0, 2.0], 12.0);
+        program.add_constraint(vec![3.0, 2.0], 18.0);
+        assert_optimum(&program, &[2.0, 6.0], 36.0);
+    }
+
+    #[test]
+    fn detects_unbounded_program() {
+        let mut program = LinearProgram::new(vec![1.0, 0.0]);
+        program.add_constraint(vec![-1.0, 1.0], 1.0);
+        assert!(matches!(
+            program.maximize(),
+            LinearProgramSolution::Unbounded
+        ));
+    }
+
+    #[test]
+    fn rejects_negative_bounds() {
+        let mut program = LinearProgram::new(vec![1.0]);
+        program.add_constraint(vec![1.0], -1.0);
+        assert!(matches!(
+            program.maximize(),
+            LinearProgramSolution::Infeasible
+        ));
+    }
+
+    // Beale's example cycles with the largest coefficient rule, Bland's rule breaks the ties of
+    // the degenerate pivots
+    #[test]
+    fn terminates_on_degenerate_program() {
+        let mut program = LinearProgram::new(vec![0.75, -20.0, 0.5, -6.0]);
+        program.add_constraint(vec![0.25, -8.0, -1.0, 9.0], 0.0);
+        program.add_constraint(vec![0.5, -12.0, -0.5, 3.0], 0.0);
+        program.add_upper_bound(2, 1.0);
+        assert_optimum(&program, &[1.0, 0.0, 1.0, 0.0], 1.25);
+    }
+}
@@ -0,0 +1,121 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use econo_sim::analysis::production_graph::ProductionGraph;
+use econo_sim::analysis::production_plan::{
+    get_processor_allocation, print_plan_comparison, ProductionPlanner,
+};
+use econo_sim::analysis::profitability::{
+    analyze_recipes, print_profitability_report, PriceSource, PriceVector,
+};
+use econo_sim::persistence::Persistence;
+use simple_logger::SimpleLogger;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Path to load world from
+    #[arg(short, long, default_value_t = String::from("data/generated_world.yml"))]
+    in_file: String,
+    /// Number of ticks to simulate before the analysis
+    #[arg(short, long, default_value_t = 0)]
+    ticks: usize,
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Input cost, output value, margin and payback time of every recipe
+    Profitability {
+        /// Price sources in order of preference
+        #[arg(
+            short,
+            long,
+            value_delimiter = ',',
+            default_value = "last-price,best-ask,producer"
+        )]
+        prices: Vec<PriceSourceArg>,
+    },
+    /// Export the production graph of resources and recipes
+    Graph {
+        #[arg(short, long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// Path to write the graph to, printed if not given
+        #[arg(short, long)]
+        out_file: Option<String>,
+    },
+    /// Check the production graph for unreachable resources, cycles and misnamed recipes
+    Check,
+    /// Profit-maximizing steady-state production plan compared to the companies' processors
+    Plan {
+        /// Ticks over which the price of a processor is spread
+        #[arg(short, long, default_value_t = 1000.0)]
+        amortization_ticks: f64,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum PriceSourceArg {
+    BestAsk,
+    BestBid,
+    LastPrice,
+    Producer,
+}
+
+impl From<PriceSourceArg> for PriceSource {
+    fn from(price_source: PriceSourceArg) -> Self {
+        match price_source {
+            PriceSourceArg::BestAsk => PriceSource::BestAsk,
+            PriceSourceArg::BestBid => PriceSource::BestBid,
+            PriceSourceArg::LastPrice => PriceSource::LastPrice,
+            PriceSourceArg::Producer => PriceSource::Producer,
+        }
+    }
+}
+
+fn main() {
+    let cli_args = Args::parse();
+    SimpleLogger::new().init().unwrap();
+    log::info!("=== ANALYSIS ===");
+    let mut world = Persistence::load_world_from(&cli_args.in_file);
+    for _ in 0..cli_args.ticks {
+        world.tick(false, 0.0);
+    }
+    match cli_args.command {
+        Command::Profitability { prices } => {
+            let sources: Vec<PriceSource> = prices.into_iter().map(PriceSource::from).collect();
+            let prices = PriceVector::from_sources(&world, &sources);
+            let profitabilities =
+                analyze_recipes(&world.recipe_data, &world.processor_data, &prices);
+            print_profitability_report(&profitabilities, &world.recipe_data, &world.resource_data);
+        }
+        Command::Graph { format, out_file } => {
+            let graph = ProductionGraph::from_world(&world);
+            let output = match format {
+                GraphFormat::Dot => graph.to_dot(),
+                GraphFormat::Mermaid => graph.to_mermaid(),
+            };
+            match out_file {
+                Some(out_file) => {
+                    std::fs::write(&out_file, output).unwrap();
+                    log::info!("Wrote production graph to {}", out_file);
+                }
+                None => print!("{}", output),
+            }
+        }
+        Command::Check => {
+            ProductionGraph::from_world(&world).print_checks();
+        }
+        Command::Plan { amortization_ticks } => {
+            let planner = ProductionPlanner::from_world(&world, amortization_ticks);
+            let actual_allocation =
+                get_processor_allocation(&world.company_data, world.recipe_data.recipes.len());
+            print_plan_comparison(&planner, &actual_allocation, &world.resource_data);
+        }
+    }
+}
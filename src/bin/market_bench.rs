@@ -0,0 +1,109 @@
+use clap::Parser;
+use econo_sim::economy::government::Government;
+use econo_sim::market::marketplace::Marketplace;
+use econo_sim::market::offer::Offer;
+use econo_sim::market::order::Order;
+use econo_sim::market::order_type::OrderType;
+use econo_sim::world_data::market_data::MarketData;
+use format_num::NumberFormat;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use simple_logger::SimpleLogger;
+use std::time::Instant;
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Number of open offers and open orders each
+    #[arg(short, long, default_value_t = 10000)]
+    listings: usize,
+    /// Number of resources the listings are spread over
+    #[arg(short, long, default_value_t = 10)]
+    resource_count: usize,
+    /// Number of crossing orders matched against the open offers
+    #[arg(short, long, default_value_t = 1000)]
+    crossing_orders: usize,
+    /// Number of market ticks to measure
+    #[arg(short, long, default_value_t = 100)]
+    ticks: usize,
+}
+
+fn main() {
+    let cli_args = Args::parse();
+    SimpleLogger::new().init().unwrap();
+    log::info!("=== MARKET BENCHMARK ===");
+    let num = NumberFormat::new();
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut market_data = MarketData::new(cli_args.resource_count);
+    market_data.resource_count = cli_args.resource_count;
+    let mut market_place = Marketplace::new();
+    let mut government = Government::new();
+
+    // Offers and orders do not cross, so all of them stay open
+    let start = Instant::now();
+    for _ in 0..cli_args.listings {
+        let offer = Offer {
+            resource: rng.gen_range(0..cli_args.resource_count),
+            amount: rng.gen_range(1.0..10.0),
+            price_per_unit: rng.gen_range(100.0..200.0),
+            company: None,
+            time_to_live: usize::MAX,
+            household: None,
+            order_type: OrderType::Limit,
+        };
+        market_place.place_offer(offer, &mut market_data);
+        let order = Order {
+            company: None,
+            resource: rng.gen_range(0..cli_args.resource_count),
+            amount: rng.gen_range(1.0..10.0),
+            max_price_per_unit: rng.gen_range(1.0..100.0),
+            time_to_live: usize::MAX,
+            order_type: OrderType::Limit,
+        };
+        market_place.place_order(order, &mut market_data);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    log::info!(
+        "Placed {} listings with {} listings/s",
+        2 * cli_args.listings,
+        num.format(".4s", 2.0 * cli_args.listings as f64 / elapsed)
+    );
+
+    // Ticks without any match only maintain the books
+    let start = Instant::now();
+    for _ in 0..cli_args.ticks {
+        market_place.tick(&mut market_data, &mut [], &mut [], &mut government);
+    }
+    let elapsed = start.elapsed().as_secs_f64();
+    log::info!(
+        "Ticked {} open listings with {} ticks/s",
+        market_data.offers.len() + market_data.orders.len(),
+        num.format(".4s", cli_args.ticks as f64 / elapsed)
+    );
+
+    // Crossing orders are matched against the cheapest offers
+    let start = Instant::now();
+    for _ in 0..cli_args.crossing_orders {
+        let order = Order {
+            company: None,
+            resource: rng.gen_range(0..cli_args.resource_count),
+            amount: rng.gen_range(1.0..10.0),
+            max_price_per_unit: rng.gen_range(150.0..250.0),
+            time_to_live: usize::MAX,
+            order_type: OrderType::Limit,
+        };
+        market_place.place_order(order, &mut market_data);
+    }
+    market_place.tick(&mut market_data, &mut [], &mut [], &mut government);
+    let elapsed = start.elapsed().as_secs_f64();
+    log::info!(
+        "Matched {} crossing orders with {} orders/s",
+        cli_args.crossing_orders,
+        num.format(".4s", cli_args.crossing_orders as f64 / elapsed)
+    );
+    log::info!(
+        "{} offers and {} orders remain open",
+        market_data.offers.len(),
+        market_data.orders.len()
+    );
+}
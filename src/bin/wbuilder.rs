@@ -1,24 +1,31 @@
-use clap::{arg, command, Parser};
+use clap::Parser;
 use econo_sim::economy::company::Company;
 use econo_sim::economy::consumer::Consumer;
+use econo_sim::economy::government::Government;
+use econo_sim::economy::household::Household;
 use econo_sim::economy::processor::Processor;
 use econo_sim::economy::producer::Producer;
 use econo_sim::economy::recipe::Recipe;
 use econo_sim::economy::stock::Stock;
-use econo_sim::economy::{company, resource};
+use econo_sim::market::market_fees::MarketFees;
+use econo_sim::market::market_mode::MarketMode;
 use econo_sim::market::marketplace::Marketplace;
 use econo_sim::market::offer::UnprocessedOffer;
 use econo_sim::market::order::UnprocessedOrder;
+use econo_sim::market::order_type::OrderType;
 use econo_sim::persistence::Persistence;
-use econo_sim::reinforcement_learning::action::ActionSpace;
+use econo_sim::reinforcement_learning::action::{ActionSpace, ActionSpaceDefinition};
+use econo_sim::reinforcement_learning::controller::ControllerType;
 use econo_sim::reinforcement_learning::state::CompanyState;
 use econo_sim::world::World;
 use econo_sim::world_data::consumer_data::ConsumerData;
+use econo_sim::world_data::labor_data::LaborData;
 use econo_sim::world_data::market_data::MarketData;
+use econo_sim::world_data::processor_data::ProcessorData;
 use econo_sim::world_data::producer_data::ProducerData;
 use econo_sim::world_data::recipe_data::RecipeData;
+use econo_sim::world_data::region_data::{Region, RegionData, RegionHandle, TransportRoute};
 use econo_sim::world_data::resource_data::ResourceData;
-use itertools::Itertools;
 use serde::{Deserialize, Serialize};
 use simple_logger::SimpleLogger;
 use std::collections::HashMap;
@@ -33,12 +40,28 @@ struct CompanyStartingConditions {
     stock: CompanyStartingConditionsStock,
     currency: f64,
     processors: Vec<Processor>,
+    #[serde(default)]
+    employees: f64,
+    #[serde(default)]
+    wage: f64,
+    // Companies are assigned to these regions in turn
+    #[serde(default)]
+    regions: Vec<String>,
+    #[serde(default)]
+    controller: ControllerType,
+    // Companies are assigned to these controllers in turn, overrides the controller
+    #[serde(default)]
+    controllers: Vec<ControllerType>,
 }
 
 struct RenderedCompanyStartingConditions {
     stock: Stock,
     currency: f64,
     processors: Vec<Processor>,
+    employees: f64,
+    wage: f64,
+    regions: Vec<RegionHandle>,
+    controllers: Vec<ControllerType>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -54,6 +77,15 @@ struct RecipeDataInput {
     pub recipes: Vec<RecipeInput>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct ProcessorDataInput {
+    pub processor_price: f64,
+    #[serde(default)]
+    pub recipe_prices: HashMap<String, f64>,
+    #[serde(default = "ProcessorData::default_scrap_value_factor")]
+    pub scrap_value_factor: f64,
+}
+
 #[derive(Serialize, Deserialize, Default, Clone)]
 pub struct UnrenderedUnprocessedOffer {
     pub resource: String,
@@ -68,6 +100,8 @@ pub struct ProducerInput {
     pub offers: Vec<UnrenderedUnprocessedOffer>,
     offer_creation_ticks: usize,
     current_tick: usize,
+    #[serde(default)]
+    region: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -89,6 +123,8 @@ pub struct ConsumerInput {
     pub orders: Vec<UnrenderedUnprocessedOrder>,
     order_creation_ticks: usize,
     current_tick: usize,
+    #[serde(default)]
+    region: Option<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -96,6 +132,60 @@ pub struct ConsumerDataInput {
     consumers: Vec<ConsumerInput>,
 }
 
+#[derive(Serialize, Deserialize)]
+pub struct HouseholdInput {
+    workers: f64,
+    reservation_wage: f64,
+    minimum_wage: f64,
+    wage_adjustment_rate: f64,
+    target_employment_rate: f64,
+    #[serde(default)]
+    region: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct LaborDataInput {
+    labor_resource: String,
+    hours_per_worker: f64,
+    households: Vec<HouseholdInput>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct GovernmentInput {
+    vat_rate: f64,
+    profit_tax_rate: f64,
+    property_tax_rate: f64,
+    subsidies: HashMap<String, f64>,
+    #[serde(default)]
+    export_tariffs: HashMap<String, f64>,
+    #[serde(default)]
+    import_tariffs: HashMap<String, f64>,
+    tax_period: usize,
+    budget: f64,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TransportRouteInput {
+    from: String,
+    to: String,
+    cost_per_unit: f64,
+    delay: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RegionDataInput {
+    home_region: String,
+    regions: Vec<String>,
+    routes: Vec<TransportRouteInput>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct MarketplaceInput {
+    #[serde(default)]
+    market_mode: MarketMode,
+    fees: MarketFees,
+}
+
 #[derive(Parser, Debug)]
 #[command(author, version, about, long_about = None)]
 struct Args {
@@ -108,6 +198,9 @@ struct Args {
     /// Path to consumer file
     #[arg(short, long, default_value_t =  String::from("data/consumer.yml"))]
     consumer_file: String,
+    /// Path to marketplace file
+    #[arg(short, long, default_value_t =  String::from("data/marketplace.yml"))]
+    marketplace_file: String,
     /// Path to processor file
     #[arg(short, long, default_value_t =  String::from("data/processor.yml"))]
     processor_file: String,
@@ -120,6 +213,24 @@ struct Args {
     /// Path to resources file
     #[arg(short, long, default_value_t =  String::from("data/resources.yml"))]
     resources_file: String,
+    /// Path to labor file
+    #[arg(short, long, default_value_t =  String::from("data/labor.yml"))]
+    labor_file: String,
+    /// Path to government file
+    #[arg(short, long, default_value_t =  String::from("data/government.yml"))]
+    government_file: String,
+    /// Path to storage file
+    #[arg(short, long, default_value_t =  String::from("data/storage.yml"))]
+    storage_file: String,
+    /// Path to regions file
+    #[arg(long, default_value_t =  String::from("data/regions.yml"))]
+    regions_file: String,
+    /// Path to contracts file
+    #[arg(long, default_value_t =  String::from("data/contracts.yml"))]
+    contracts_file: String,
+    /// Path to action space definition file
+    #[arg(long, default_value_t =  String::from("data/actions.yml"))]
+    actions_file: String,
     /// Path to save generated world to
     #[arg(short, long, default_value_t =  String::from("data/generated_world.yml"))]
     out_file: String,
@@ -150,7 +261,65 @@ fn render_recipe_data(recipes_file: String, resource_data: &ResourceData) -> Rec
     recipe_data
 }
 
-fn render_producer_data(producers_file: String, resource_data: &ResourceData) -> ProducerData {
+fn render_processor_data(processor_file: String, recipe_data: &RecipeData) -> ProcessorData {
+    let unrendered: ProcessorDataInput = Persistence::load_from(&processor_file);
+    // Create processor data
+    let mut processor_data = ProcessorData::new();
+    processor_data.processor_price = unrendered.processor_price;
+    processor_data.scrap_value_factor = unrendered.scrap_value_factor;
+    // Render recipe prices
+    for (recipe, price) in unrendered.recipe_prices.iter() {
+        processor_data.recipe_prices.insert(
+            recipe_data.get_recipe_handle_by_name(recipe).unwrap(),
+            *price,
+        );
+    }
+    processor_data
+}
+
+fn render_region(region: &Option<String>, region_data: &RegionData) -> RegionHandle {
+    match region {
+        Some(region) => region_data.get_region_handle_by_name(region).unwrap(),
+        None => 0,
+    }
+}
+
+fn render_region_data(
+    regions_file: String,
+    resource_count: usize,
+    marketplace_file: &str,
+) -> RegionData {
+    let unrendered: RegionDataInput = Persistence::load_from(&regions_file);
+    // Create region data
+    let mut region_data = RegionData::new();
+    region_data.home_region_name = unrendered.home_region;
+    // Every region has its own market with the same rules
+    for region in unrendered.regions.iter() {
+        let mut market_data = MarketData::new(resource_count);
+        market_data.resource_count = resource_count;
+        region_data.regions.push(Region {
+            name: region.clone(),
+            market_data,
+            market_place: render_marketplace(marketplace_file.to_string()),
+        });
+    }
+    // Render routes
+    for route in unrendered.routes.iter() {
+        region_data.routes.push(TransportRoute {
+            from: region_data.get_region_handle_by_name(&route.from).unwrap(),
+            to: region_data.get_region_handle_by_name(&route.to).unwrap(),
+            cost_per_unit: route.cost_per_unit,
+            delay: route.delay,
+        });
+    }
+    region_data
+}
+
+fn render_producer_data(
+    producers_file: String,
+    resource_data: &ResourceData,
+    region_data: &RegionData,
+) -> ProducerData {
     let unrendered: ProducerDataInput = Persistence::load_from(&producers_file);
     // Create producer data
     let mut producer_data = ProducerData::new();
@@ -159,6 +328,7 @@ fn render_producer_data(producers_file: String, resource_data: &ResourceData) ->
         let mut tmp_producer = Producer::new();
         tmp_producer.current_tick = producer.current_tick;
         tmp_producer.offer_creation_ticks = producer.offer_creation_ticks;
+        tmp_producer.region = render_region(&producer.region, region_data);
         // Create offers
         for offer in producer.offers.iter() {
             tmp_producer.offers.push(UnprocessedOffer {
@@ -168,6 +338,7 @@ fn render_producer_data(producers_file: String, resource_data: &ResourceData) ->
                 amount: offer.amount,
                 price_per_unit: offer.price_per_unit,
                 time_to_live: offer.time_to_live,
+                order_type: OrderType::Limit,
             });
         }
         // Create production
@@ -179,6 +350,7 @@ fn render_producer_data(producers_file: String, resource_data: &ResourceData) ->
                 amount: production.amount,
                 price_per_unit: production.price_per_unit,
                 time_to_live: production.time_to_live,
+                order_type: OrderType::Limit,
             });
         }
         producer_data.producers.push(tmp_producer);
@@ -186,7 +358,11 @@ fn render_producer_data(producers_file: String, resource_data: &ResourceData) ->
     producer_data
 }
 
-fn render_consumer_data(consumers_file: String, resource_data: &ResourceData) -> ConsumerData {
+fn render_consumer_data(
+    consumers_file: String,
+    resource_data: &ResourceData,
+    region_data: &RegionData,
+) -> ConsumerData {
     let unrendered: ConsumerDataInput = Persistence::load_from(&consumers_file);
     // Create consumer data
     let mut consumer_data = ConsumerData::new();
@@ -195,6 +371,7 @@ fn render_consumer_data(consumers_file: String, resource_data: &ResourceData) ->
         let mut tmp_consumer = Consumer::new();
         tmp_consumer.current_tick = consumer.current_tick;
         tmp_consumer.order_creation_ticks = consumer.order_creation_ticks;
+        tmp_consumer.region = render_region(&consumer.region, region_data);
         // Create offers
         for order in consumer.orders.iter() {
             tmp_consumer.orders.push(UnprocessedOrder {
@@ -204,6 +381,7 @@ fn render_consumer_data(consumers_file: String, resource_data: &ResourceData) ->
                 amount: order.amount,
                 max_price_per_unit: order.max_price_per_unit,
                 time_to_live: order.time_to_live,
+                order_type: OrderType::Limit,
             });
         }
         // Create production
@@ -215,6 +393,7 @@ fn render_consumer_data(consumers_file: String, resource_data: &ResourceData) ->
                 amount: consumption.amount,
                 max_price_per_unit: consumption.max_price_per_unit,
                 time_to_live: consumption.time_to_live,
+                order_type: OrderType::Limit,
             });
         }
         consumer_data.consumers.push(tmp_consumer);
@@ -222,9 +401,79 @@ fn render_consumer_data(consumers_file: String, resource_data: &ResourceData) ->
     consumer_data
 }
 
+fn render_labor_data(
+    labor_file: String,
+    resource_data: &ResourceData,
+    region_data: &RegionData,
+) -> LaborData {
+    let unrendered: LaborDataInput = Persistence::load_from(&labor_file);
+    // Create labor data
+    let mut labor_data = LaborData::new();
+    labor_data.labor_resource =
+        resource_data.get_resource_handle_by_name(&unrendered.labor_resource);
+    labor_data.hours_per_worker = unrendered.hours_per_worker;
+    // Create households
+    for household in unrendered.households.iter() {
+        let mut tmp_household = Household::new();
+        tmp_household.workers = household.workers;
+        tmp_household.reservation_wage = household.reservation_wage;
+        tmp_household.minimum_wage = household.minimum_wage;
+        tmp_household.wage_adjustment_rate = household.wage_adjustment_rate;
+        tmp_household.target_employment_rate = household.target_employment_rate;
+        tmp_household.region = render_region(&household.region, region_data);
+        labor_data.households.push(tmp_household);
+    }
+    labor_data
+}
+
+fn render_government(
+    government_file: String,
+    recipe_data: &RecipeData,
+    region_data: &RegionData,
+) -> Government {
+    let unrendered: GovernmentInput = Persistence::load_from(&government_file);
+    // Create government
+    let mut government = Government::new();
+    government.vat_rate = unrendered.vat_rate;
+    government.profit_tax_rate = unrendered.profit_tax_rate;
+    government.property_tax_rate = unrendered.property_tax_rate;
+    government.tax_period = unrendered.tax_period;
+    government.budget = unrendered.budget;
+    // Render subsidies
+    for (recipe, subsidy) in unrendered.subsidies.iter() {
+        government.subsidies.insert(
+            recipe_data.get_recipe_handle_by_name(recipe).unwrap(),
+            *subsidy,
+        );
+    }
+    // Render tariffs
+    for (region, rate) in unrendered.export_tariffs.iter() {
+        government.export_tariffs.insert(
+            region_data.get_region_handle_by_name(region).unwrap(),
+            *rate,
+        );
+    }
+    for (region, rate) in unrendered.import_tariffs.iter() {
+        government.import_tariffs.insert(
+            region_data.get_region_handle_by_name(region).unwrap(),
+            *rate,
+        );
+    }
+    government
+}
+
+fn render_marketplace(marketplace_file: String) -> Marketplace {
+    let unrendered: MarketplaceInput = Persistence::load_from(&marketplace_file);
+    let mut market_place = Marketplace::new();
+    market_place.market_mode = unrendered.market_mode;
+    market_place.fees = unrendered.fees;
+    market_place
+}
+
 fn render_company_starting_conditions(
     company_starting_conditions_file: String,
     resource_data: &ResourceData,
+    region_data: &RegionData,
 ) -> RenderedCompanyStartingConditions {
     let unrendered: CompanyStartingConditions =
         Persistence::load_from(&company_starting_conditions_file);
@@ -237,9 +486,21 @@ fn render_company_starting_conditions(
         )
     }
     RenderedCompanyStartingConditions {
-        stock: stock,
+        stock,
         currency: unrendered.currency,
         processors: unrendered.processors,
+        employees: unrendered.employees,
+        wage: unrendered.wage,
+        regions: unrendered
+            .regions
+            .iter()
+            .map(|region| region_data.get_region_handle_by_name(region).unwrap())
+            .collect(),
+        controllers: if unrendered.controllers.is_empty() {
+            vec![unrendered.controller]
+        } else {
+            unrendered.controllers
+        },
     }
 }
 
@@ -252,27 +513,68 @@ fn main() {
     // Load resource data
     world.resource_data = Persistence::load_from(&cli_args.resources_file);
     let resource_count = world.resource_data.resources.len();
+    // Load region data
+    world.region_data = render_region_data(
+        cli_args.regions_file,
+        resource_count,
+        &cli_args.marketplace_file,
+    );
     // Load consumers data
-    world.consumer_data = render_consumer_data(cli_args.consumer_file, &world.resource_data);
+    world.consumer_data = render_consumer_data(
+        cli_args.consumer_file,
+        &world.resource_data,
+        &world.region_data,
+    );
     // Create market data
     world.market_data = MarketData::new(resource_count);
     // Load marketplace data
-    world.market_place = Marketplace::new();
-    // Load processor data
-    world.processor_data = Persistence::load_from(&cli_args.processor_file);
+    world.market_place = render_marketplace(cli_args.marketplace_file);
     // Load producer data
-    world.producer_data = render_producer_data(cli_args.producer_file, &world.resource_data);
+    world.producer_data = render_producer_data(
+        cli_args.producer_file,
+        &world.resource_data,
+        &world.region_data,
+    );
     // Load recipe data
     world.recipe_data = render_recipe_data(cli_args.recipes_file, &world.resource_data);
+    // Load processor data
+    world.processor_data = render_processor_data(cli_args.processor_file, &world.recipe_data);
     // Adjust resource count
     world.market_data.resource_count = resource_count;
+    // Load labor data
+    world.labor_data = render_labor_data(
+        cli_args.labor_file,
+        &world.resource_data,
+        &world.region_data,
+    );
+    // Load government
+    world.government = render_government(
+        cli_args.government_file,
+        &world.recipe_data,
+        &world.region_data,
+    );
+    // Load storage data
+    world.storage_data = Persistence::load_from(&cli_args.storage_file);
+    // Load contract data
+    world.contract_data = Persistence::load_from(&cli_args.contracts_file);
     // Load company starting conditions
     let company_starting_conditions = render_company_starting_conditions(
         cli_args.company_starting_conditions_file,
         &world.resource_data,
+        &world.region_data,
     );
     // Create actionspace
-    let actionspace = ActionSpace::new(resource_count, world.recipe_data.recipes.len());
+    let actionspace_definition: ActionSpaceDefinition =
+        Persistence::load_from(&cli_args.actions_file);
+    let mut actionspace = ActionSpace::new(
+        &actionspace_definition,
+        resource_count,
+        world.recipe_data.recipes.len(),
+        world.labor_data.labor_resource,
+    );
+    if world.region_data.get_region_count() > 1 {
+        actionspace.add_export_actions(world.region_data.get_region_count());
+    }
     world.actionspace = actionspace;
     let actionspace_dimensions = world.actionspace.actions.len();
     // Define start state
@@ -281,10 +583,17 @@ fn main() {
         start_state.stock[*resource] = *amount as usize;
     }
     start_state.currency = company_starting_conditions.currency as usize;
+    start_state.employees = company_starting_conditions.employees as usize;
     // Define state dimenstions
     let statespace_dimensions = start_state.as_f64_vec().len();
     log::info!("Resource count: {}", resource_count);
     log::info!("Actionspace dimensions: {}", actionspace_dimensions);
+    if let Some(hybrid) = &world.actionspace.hybrid {
+        log::info!(
+            "Parameterized actions: {}",
+            hybrid.parameterized_actions.len()
+        );
+    }
     log::info!("Statespace dimensions: {}", statespace_dimensions);
     // Create companies
     let mut companies: Vec<Company> = (0..cli_args.company_count)
@@ -296,12 +605,20 @@ fn main() {
                 statespace_dimensions as i32,
                 actionspace_dimensions as i32,
                 0.9,
+                company_starting_conditions.controllers
+                    [x % company_starting_conditions.controllers.len()],
             )
         })
         .map(|mut x| {
             x.stock = company_starting_conditions.stock.clone();
             x.processors = company_starting_conditions.processors.clone();
             x.currency = company_starting_conditions.currency;
+            x.employees = company_starting_conditions.employees;
+            x.wage = company_starting_conditions.wage;
+            if !company_starting_conditions.regions.is_empty() {
+                let regions = &company_starting_conditions.regions;
+                x.region = regions[x.id % regions.len()];
+            }
             x.old_state = start_state.clone();
             x
         })
@@ -1,15 +1,30 @@
+use crate::economy::company_statistics::CompanyStatistics;
+use crate::economy::contract::{Position, SupplyContractRequest, UnprocessedFuture};
 use crate::economy::processor::Processor;
 use crate::economy::recipe::RecipeHandle;
 use crate::economy::resource::ResourceHandle;
 use crate::economy::stock::Stock;
-use crate::market::offer::UnprocessedOffer;
-use crate::market::order::UnprocessedOrder;
-use crate::reinforcement_learning::action::ActionSpace;
+use crate::market::book_request::BookRequest;
+use crate::market::offer::{OfferHandle, UnprocessedOffer};
+use crate::market::order::{OrderHandle, UnprocessedOrder};
+use crate::market::order_type::OrderType;
+use crate::market::price_reference::PriceReference;
+use crate::market::processor_offer::{UnprocessedProcessorOffer, UnprocessedProcessorOrder};
+use crate::market::shipment::UnprocessedShipment;
 use crate::reinforcement_learning::action::CompanyAction;
-use crate::reinforcement_learning::deep_rl_agent::DeepRLAgent;
-use crate::reinforcement_learning::state::CompanyState;
+use crate::reinforcement_learning::action::{
+    ActionSpace, HybridAction, HybridActionSpace, ParameterizedAction,
+};
+use crate::reinforcement_learning::controller::{CompanyController, ControllerType};
+use crate::reinforcement_learning::heuristic::HeuristicContext;
+use crate::reinforcement_learning::state::{CompanyState, FEATURE_WINDOW};
 use crate::world_data::market_data::MarketData;
+use crate::world_data::processor_data::ProcessorData;
 use crate::world_data::recipe_data::RecipeData;
+use crate::world_data::region_data::{RegionData, RegionHandle};
+use crate::world_data::resource_data::ResourceData;
+use crate::world_data::storage_data::{OverflowBehavior, StorageData};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 pub type CompanyHandle = usize;
 
@@ -21,11 +36,48 @@ pub struct Company {
     pub processors: Vec<Processor>,
     pub orders: Vec<UnprocessedOrder>,
     pub offers: Vec<UnprocessedOffer>,
+    #[serde(default)]
+    pub book_requests: Vec<BookRequest>,
+    #[serde(default)]
+    pub open_orders: Vec<OrderHandle>,
+    #[serde(default)]
+    pub open_offers: Vec<OfferHandle>,
+    #[serde(default)]
+    pub processor_offers: Vec<UnprocessedProcessorOffer>,
+    #[serde(default)]
+    pub processor_orders: Vec<UnprocessedProcessorOrder>,
     pub company_value: f64,
+    // Value of the holdings of each resource at the last tick
+    #[serde(default)]
+    old_resource_values: Vec<f64>,
     pub id: CompanyHandle,
-    pub agent: DeepRLAgent,
+    #[serde(deserialize_with = "CompanyController::deserialize_stored")]
+    pub agent: CompanyController,
     pub old_state: CompanyState,
     old_company_value: f64,
+    #[serde(default)]
+    pub warehouses: usize,
+    #[serde(default)]
+    pub statistics: CompanyStatistics,
+    // Highest company value the profit tax was assessed on
+    #[serde(default)]
+    pub profit_tax_baseline: Option<f64>,
+    #[serde(default)]
+    pub employees: f64,
+    // Maximum wage per worker-hour
+    #[serde(default)]
+    pub wage: f64,
+    #[serde(default)]
+    pub region: RegionHandle,
+    #[serde(default)]
+    pub exports: Vec<UnprocessedShipment>,
+    #[serde(default)]
+    pub futures: Vec<UnprocessedFuture>,
+    #[serde(default)]
+    pub supply_requests: Vec<SupplyContractRequest>,
+    // Currency held as margin for open forward contracts
+    #[serde(default)]
+    pub posted_margin: f64,
 }
 
 impl Company {
@@ -36,6 +88,7 @@ impl Company {
         state_dimensions: i32,
         action_dimensions: i32,
         discount: f64,
+        controller_type: ControllerType,
     ) -> Self {
         Company {
             name: name.to_string(),
@@ -44,81 +97,329 @@ impl Company {
             processors: vec![],
             orders: vec![],
             offers: vec![],
+            book_requests: vec![],
+            open_orders: vec![],
+            open_offers: vec![],
+            processor_offers: vec![],
+            processor_orders: vec![],
             company_value: 0.0,
+            old_resource_values: vec![],
             id: company_handle,
-            agent: DeepRLAgent::new(state_dimensions, action_dimensions, discount),
+            agent: CompanyController::new(
+                controller_type,
+                state_dimensions,
+                action_dimensions,
+                discount,
+            ),
             old_state: CompanyState::new(resource_count),
             old_company_value: 0.0,
+            warehouses: 0,
+            statistics: CompanyStatistics::new(),
+            profit_tax_baseline: None,
+            employees: 0.0,
+            wage: 0.0,
+            region: 0,
+            exports: vec![],
+            futures: vec![],
+            supply_requests: vec![],
+            posted_margin: 0.0,
         }
     }
 
-    pub fn tick(
+    pub fn produce(
         &mut self,
         recipe_data: &RecipeData,
-        market_data: &MarketData,
-        processor_price: f64,
+        storage_data: &StorageData,
+        resource_data: &ResourceData,
+    ) -> Vec<RecipeHandle> {
+        let mut productions: Vec<RecipeHandle> = vec![];
+        for processor in self.processors.iter() {
+            if storage_data.overflow_behavior == OverflowBehavior::RejectProduction {
+                let recipe = recipe_data.get_recipe_by_handle(processor.recipe).unwrap();
+                if !storage_data.can_store_recipe_output(
+                    recipe,
+                    processor.production_speed,
+                    &self.stock,
+                    resource_data,
+                    self.warehouses,
+                ) {
+                    self.statistics.rejected_productions += 1;
+                    continue;
+                }
+            }
+            if processor.tick(&mut self.stock, recipe_data) {
+                productions.push(processor.recipe);
+            }
+        }
+        productions
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn tick<R: Rng>(
+        &mut self,
+        recipe_data: &RecipeData,
+        home_market_data: &MarketData,
+        region_data: &RegionData,
+        processor_data: &mut ProcessorData,
+        storage_data: &StorageData,
         actionspace: &ActionSpace,
         train: bool,
         exploration_factor: f64,
+        rng: &mut R,
     ) {
-        for processor in self.processors.iter() {
-            processor.tick(&mut self.stock, recipe_data);
-        }
+        let market_data = region_data.get_market_data(self.region, home_market_data);
+        // Forget about filled and expired listings
+        self.open_orders
+            .retain(|order_handle| market_data.orders.contains_key(order_handle));
+        self.open_offers
+            .retain(|offer_handle| market_data.offers.contains_key(offer_handle));
         // Construct company state
         let company_state = CompanyState {
-            stock: self.stock.resources.values().map(|x| *x as usize).collect(),
+            stock: (0..market_data.resource_count)
+                .map(|x| self.stock.get_amount(x) as usize)
+                .collect(),
             currency: self.currency as usize,
-            price_index: market_data
-                .price_index
-                .values()
-                .map(|x: &Option<(usize, f64)>| {
-                    if x.is_some() {
-                        x.unwrap().1 as usize
-                    } else {
-                        0
-                    }
+            price_index: (0..market_data.resource_count)
+                .map(|x| match market_data.price_index.get(&x) {
+                    Some(Some((_, price))) => *price as usize,
+                    _ => 0,
                 })
                 .collect(),
-            order_index: market_data
-                .order_index
-                .values()
-                .map(|x: &Option<(usize, f64)>| {
-                    if x.is_some() {
-                        x.unwrap().1 as usize
-                    } else {
-                        0
-                    }
+            order_index: (0..market_data.resource_count)
+                .map(|x| match market_data.order_index.get(&x) {
+                    Some(Some((_, price))) => *price as usize,
+                    _ => 0,
                 })
                 .collect(),
+            employees: self.employees as usize,
+            last_price: (0..market_data.resource_count)
+                .map(|x| market_data.get_last_price(x).unwrap_or(0.0) as usize)
+                .collect(),
+            vwap: (0..market_data.resource_count)
+                .map(|x| market_data.get_vwap(x, FEATURE_WINDOW).unwrap_or(0.0) as usize)
+                .collect(),
+            traded_volume: (0..market_data.resource_count)
+                .map(|x| market_data.get_traded_volume(x, FEATURE_WINDOW) as usize)
+                .collect(),
         };
 
         self.old_company_value = self.company_value;
-        self.company_value = self.calculate_company_value(market_data, processor_price);
+        self.company_value =
+            self.calculate_company_value(home_market_data, region_data, processor_data);
 
+        let resource_values = self.calculate_resource_values(market_data);
+        let action_groups = actionspace.get_action_groups();
         if train {
-            self.agent.train(
-                self.old_state.as_f64_vec(),
+            let group_rewards = self.get_group_rewards(
                 self.company_value - self.old_company_value - 1.0,
-                company_state.as_f64_vec(),
+                &resource_values,
+                market_data,
+                actionspace,
+            );
+            self.agent.train(
+                &self.old_state,
+                &group_rewards,
+                &company_state,
+                &action_groups,
             );
         }
-        let action = self
-            .agent
-            .get_next_state_action(company_state.as_f64_vec(), exploration_factor);
+        self.old_resource_values = resource_values;
+        let action_mask =
+            actionspace.get_action_mask(self, market_data, processor_data, storage_data);
+        let actions = match self.agent {
+            CompanyController::Heuristic(strategy) => strategy
+                .get_actions(
+                    &HeuristicContext::new(
+                        self,
+                        actionspace,
+                        recipe_data,
+                        market_data,
+                        processor_data,
+                    ),
+                    &action_mask,
+                    &action_groups,
+                )
+                .into_iter()
+                .map(HybridAction::Discrete)
+                .collect(),
+            _ => self.agent.get_next_state_actions(
+                &company_state,
+                exploration_factor,
+                if actionspace.definition.action_masking {
+                    &action_mask
+                } else {
+                    &[]
+                },
+                &action_groups,
+                actionspace.hybrid.as_ref(),
+                rng,
+            ),
+        };
         self.old_state = company_state;
-        // Act according to agent decision
-        match actionspace.actions[action] {
-            CompanyAction::Nothing => {
+        self.statistics.chosen_actions += actions.len();
+        self.statistics.invalid_choices += self.agent.get_last_invalid_choices();
+        if !actionspace.definition.action_masking {
+            self.statistics.invalid_choices += actions
+                .iter()
+                .filter(|action| !action_mask[action.get_action()])
+                .count();
+        }
+        // Act according to agent decisions
+        for action in actions {
+            self.execute_hybrid_action(
+                &action,
+                actionspace,
+                recipe_data,
+                market_data,
+                processor_data,
+                storage_data,
+            );
+        }
+    }
+
+    // Discrete choices of parameterized actions use the middle of the parameter ranges
+    pub fn execute_hybrid_action(
+        &mut self,
+        hybrid_action: &HybridAction,
+        actionspace: &ActionSpace,
+        recipe_data: &RecipeData,
+        market_data: &MarketData,
+        processor_data: &mut ProcessorData,
+        storage_data: &StorageData,
+    ) {
+        let action = &actionspace.actions[hybrid_action.get_action()];
+        let (parameterized_action, hybrid) = match (action, &actionspace.hybrid) {
+            (CompanyAction::Parameterized(parameterized_action), Some(hybrid)) => {
+                (*parameterized_action, hybrid)
+            }
+            _ => {
+                self.execute_action(
+                    action,
+                    recipe_data,
+                    market_data,
+                    processor_data,
+                    storage_data,
+                );
+                return;
+            }
+        };
+        let (amount, price_percentage) = match *hybrid_action {
+            HybridAction::Discrete(_) => hybrid.get_default_parameters(),
+            HybridAction::Parameterized {
+                amount,
+                price_percentage,
+                ..
+            } => hybrid.get_parameters(amount, price_percentage),
+        };
+        self.execute_parameterized_action(
+            parameterized_action,
+            amount,
+            price_percentage,
+            hybrid,
+            market_data,
+        );
+    }
+
+    fn execute_parameterized_action(
+        &mut self,
+        action: ParameterizedAction,
+        amount: f64,
+        price_percentage: f64,
+        hybrid: &HybridActionSpace,
+        market_data: &MarketData,
+    ) {
+        match action {
+            ParameterizedAction::BuyResource(resource) => self.place_relative_order(
+                resource,
+                amount,
+                price_percentage,
+                hybrid.buy_reference,
+                market_data,
+            ),
+            ParameterizedAction::SellResource(resource) => self.place_relative_offer(
+                resource,
+                amount,
+                price_percentage,
+                hybrid.sell_reference,
+                market_data,
+            ),
+        }
+    }
+
+    pub fn execute_action(
+        &mut self,
+        action: &CompanyAction,
+        recipe_data: &RecipeData,
+        market_data: &MarketData,
+        processor_data: &mut ProcessorData,
+        storage_data: &StorageData,
+    ) {
+        match *action {
+            CompanyAction::Nothing | CompanyAction::NothingWithResource(_) => {
                 // do nothing
             }
             CompanyAction::BuyProcessor(recipe) => {
                 if recipe_data.recipes.len() <= recipe {
                     return;
                 }
-                self.buy_processor(recipe, processor_price, &recipe_data);
+                self.buy_processor(recipe, processor_data, recipe_data);
             }
             CompanyAction::SellProcessor(processor) => {
-                self.sell_processor(processor, processor_price);
+                self.sell_processor(processor, processor_data);
+            }
+            CompanyAction::SellProcessorOfRecipe(recipe) => {
+                self.sell_processor_of_recipe(recipe, processor_data);
+            }
+            CompanyAction::OfferProcessor(processor, price_percentage) => {
+                if self.processors.len() <= processor {
+                    return;
+                }
+                let price = processor_data.get_processor_price(self.processors[processor].recipe)
+                    * price_percentage as f64
+                    / 100.0;
+                self.offer_processor(processor, price);
+            }
+            CompanyAction::OfferProcessorOfRecipe(recipe, price_percentage) => {
+                if let Some(processor) = self
+                    .processors
+                    .iter()
+                    .position(|processor| processor.recipe == recipe)
+                {
+                    let price = processor_data.get_processor_price(recipe)
+                        * price_percentage as f64
+                        / 100.0;
+                    self.offer_processor(processor, price);
+                }
+            }
+            CompanyAction::BuyUsedProcessor(recipe) => {
+                self.buy_used_processor(recipe, market_data);
+            }
+            CompanyAction::BuyWarehouse => {
+                self.buy_warehouse(storage_data);
+            }
+            CompanyAction::Hire(workers, wage) => {
+                self.hire(workers as f64, wage as f64);
+            }
+            CompanyAction::Fire(workers) => {
+                self.fire(workers as f64);
+            }
+            CompanyAction::CancelOrders(resource) => {
+                self.cancel_orders(resource, market_data);
+            }
+            CompanyAction::CancelOffers(resource) => {
+                self.cancel_offers(resource, market_data);
+            }
+            CompanyAction::AmendOrders(resource, max_price) => {
+                self.amend_orders(resource, max_price as f64, market_data);
+            }
+            CompanyAction::AmendOffers(resource, price) => {
+                self.amend_offers(resource, price as f64, market_data);
+            }
+            CompanyAction::AmendOrderAmounts(resource, amount) => {
+                self.amend_order_amounts(resource, amount as f64, market_data);
+            }
+            CompanyAction::AmendOfferAmounts(resource, amount) => {
+                self.amend_offer_amounts(resource, amount as f64, market_data);
             }
             CompanyAction::BuyResource(resource, amount, max_price) => {
                 self.place_order(resource, amount as f64, max_price as f64);
@@ -126,6 +427,75 @@ impl Company {
             CompanyAction::SellResource(resource, amount, price) => {
                 self.place_offer(resource, amount as f64, price as f64)
             }
+            CompanyAction::BuyResourceRelative(resource, amount, price_percentage, reference) => {
+                self.place_relative_order(
+                    resource,
+                    amount as f64,
+                    price_percentage as f64,
+                    reference,
+                    market_data,
+                );
+            }
+            CompanyAction::SellResourceRelative(resource, amount, price_percentage, reference) => {
+                self.place_relative_offer(
+                    resource,
+                    amount as f64,
+                    price_percentage as f64,
+                    reference,
+                    market_data,
+                );
+            }
+            CompanyAction::BuyResourceWithType(resource, amount, max_price, order_type) => {
+                self.place_order_with_type(resource, amount as f64, max_price as f64, order_type);
+            }
+            CompanyAction::SellResourceWithType(resource, amount, price, order_type) => {
+                self.place_offer_with_type(resource, amount as f64, price as f64, order_type);
+            }
+            CompanyAction::ExportResource(resource, amount, price, region) => {
+                self.export(resource, amount as f64, price as f64, region);
+            }
+            CompanyAction::BuyFuture(resource, amount, price, maturity) => {
+                self.place_future(
+                    Position::Long,
+                    resource,
+                    amount as f64,
+                    price as f64,
+                    maturity,
+                );
+            }
+            CompanyAction::SellFuture(resource, amount, price, maturity) => {
+                self.place_future(
+                    Position::Short,
+                    resource,
+                    amount as f64,
+                    price as f64,
+                    maturity,
+                );
+            }
+            CompanyAction::ProposeSupplyContract(
+                resource,
+                amount,
+                price,
+                interval,
+                periods,
+                position,
+            ) => {
+                self.supply_requests.push(SupplyContractRequest::Propose {
+                    position,
+                    resource,
+                    amount: amount as f64,
+                    price_per_unit: price as f64,
+                    interval,
+                    periods,
+                });
+            }
+            CompanyAction::AcceptSupplyContract(resource, position) => {
+                self.supply_requests
+                    .push(SupplyContractRequest::Accept { position, resource });
+            }
+            CompanyAction::Parameterized(_) => {
+                // needs the parameter ranges, executed by execute_hybrid_action
+            }
         }
     }
 
@@ -141,13 +511,14 @@ impl Company {
     pub fn buy_processor(
         &mut self,
         recipe: RecipeHandle,
-        processor_price: f64,
+        processor_data: &mut ProcessorData,
         recipe_data: &RecipeData,
     ) {
-        if self.currency < processor_price {
+        if self.currency < processor_data.get_processor_price(recipe) {
             return;
         }
-        self.currency -= processor_price;
+        // Pay the processor manufacturer
+        self.currency -= processor_data.sell_new_processor(recipe);
         let processor_name = String::from("Proc")
             + &recipe_data
                 .get_recipe_by_handle(recipe)
@@ -158,75 +529,470 @@ impl Company {
         let proc = Processor {
             name: processor_name,
             production_speed: 1.0,
-            recipe: recipe,
+            recipe,
             productive: true,
         };
         self.processors.push(proc);
     }
 
-    pub fn sell_processor(&mut self, processor: usize, processor_price: f64) {
+    pub fn sell_processor(&mut self, processor: usize, processor_data: &mut ProcessorData) {
+        if self.processors.len() <= processor {
+            return;
+        }
+        // Scrap processor, the manufacturer pays back its scrap value
+        let processor = self.processors.remove(processor);
+        self.currency += processor_data.scrap_processor(processor.recipe);
+    }
+
+    pub fn sell_processor_of_recipe(
+        &mut self,
+        recipe: RecipeHandle,
+        processor_data: &mut ProcessorData,
+    ) {
+        if let Some(processor) = self
+            .processors
+            .iter()
+            .position(|processor| processor.recipe == recipe)
+        {
+            self.sell_processor(processor, processor_data);
+        }
+    }
+
+    pub fn offer_processor(&mut self, processor: usize, price: f64) {
         if self.processors.len() <= processor {
             return;
         }
-        self.currency += processor_price;
-        self.processors.remove(processor);
+        self.processor_offers.push(UnprocessedProcessorOffer {
+            processor: self.processors.remove(processor),
+            price,
+            time_to_live: 100,
+        });
+    }
+
+    pub fn buy_used_processor(&mut self, recipe: RecipeHandle, market_data: &MarketData) {
+        if let Some((offer_handle, price)) =
+            market_data.get_cheapest_processor_offer(recipe, Some(self.id))
+        {
+            if self.currency < price {
+                return;
+            }
+            self.processor_orders.push(UnprocessedProcessorOrder {
+                offer: offer_handle,
+                max_price: price,
+            });
+        }
+    }
+
+    pub fn buy_warehouse(&mut self, storage_data: &StorageData) {
+        if self.currency < storage_data.warehouse_price {
+            return;
+        }
+        self.currency -= storage_data.warehouse_price;
+        self.statistics.warehouse_spending += storage_data.warehouse_price;
+        self.warehouses += 1;
+    }
+
+    pub fn apply_spoilage<R: Rng>(&mut self, resource_data: &ResourceData, rng: &mut R) {
+        for (resource, amount) in self.stock.apply_decay(resource_data, rng) {
+            self.statistics.add_spoilage(resource, amount);
+        }
+    }
+
+    pub fn pay_holding_costs(&mut self, storage_data: &StorageData, resource_data: &ResourceData) {
+        let holding_costs = storage_data.get_holding_costs(&self.stock, resource_data);
+        self.currency -= holding_costs;
+        self.statistics.holding_costs += holding_costs;
+    }
+
+    pub fn liquidate_overflow(
+        &mut self,
+        storage_data: &StorageData,
+        resource_data: &ResourceData,
+        market_data: &MarketData,
+    ) {
+        for storage_pool in storage_data.get_storage_pools() {
+            let used_capacity =
+                storage_data.get_used_capacity(storage_pool, &self.stock, resource_data);
+            let excess = used_capacity - storage_data.get_capacity(storage_pool, self.warehouses);
+            if excess <= 0.0 {
+                continue;
+            }
+            // Liquidate all resources of the storage pool proportionally
+            let liquidation_factor = excess / used_capacity;
+            for (resource, amount) in self.stock.resources.iter_mut() {
+                if storage_data.get_storage_pool(*resource, resource_data) != storage_pool {
+                    continue;
+                }
+                let liquidated_amount = *amount * liquidation_factor;
+                let revenue = liquidated_amount
+                    * market_data.get_market_price(*resource)
+                    * storage_data.forced_sale_price_factor;
+                *amount -= liquidated_amount;
+                self.currency += revenue;
+                self.statistics.forced_sale_amount += liquidated_amount;
+                self.statistics.forced_sale_revenue += revenue;
+            }
+        }
+    }
+
+    pub fn hire(&mut self, workers: f64, wage: f64) {
+        self.employees += workers;
+        self.wage = wage;
+    }
+
+    pub fn fire(&mut self, workers: f64) {
+        self.employees = (self.employees - workers).max(0.0);
+    }
+
+    pub fn place_labor_order(&mut self, labor_resource: ResourceHandle, hours_per_worker: f64) {
+        if self.employees <= 0.0 {
+            return;
+        }
+        self.orders.push(UnprocessedOrder {
+            resource: labor_resource,
+            amount: self.employees * hours_per_worker,
+            max_price_per_unit: self.wage,
+            time_to_live: 1,
+            order_type: OrderType::Limit,
+        });
+    }
+
+    pub fn cancel_orders(&mut self, resource: ResourceHandle, market_data: &MarketData) {
+        for order_handle in self.open_orders.iter() {
+            if market_data.orders[order_handle].resource == resource {
+                self.book_requests
+                    .push(BookRequest::CancelOrder(*order_handle));
+            }
+        }
+    }
+
+    pub fn cancel_offers(&mut self, resource: ResourceHandle, market_data: &MarketData) {
+        for offer_handle in self.open_offers.iter() {
+            if market_data.offers[offer_handle].resource == resource {
+                self.book_requests
+                    .push(BookRequest::CancelOffer(*offer_handle));
+            }
+        }
+    }
+
+    pub fn amend_orders(
+        &mut self,
+        resource: ResourceHandle,
+        max_price_per_unit: f64,
+        market_data: &MarketData,
+    ) {
+        for order_handle in self.open_orders.iter() {
+            let order = &market_data.orders[order_handle];
+            if order.resource == resource {
+                self.book_requests.push(BookRequest::AmendOrder(
+                    *order_handle,
+                    order.amount,
+                    max_price_per_unit,
+                ));
+            }
+        }
+    }
+
+    pub fn amend_offers(
+        &mut self,
+        resource: ResourceHandle,
+        price_per_unit: f64,
+        market_data: &MarketData,
+    ) {
+        for offer_handle in self.open_offers.iter() {
+            let offer = &market_data.offers[offer_handle];
+            if offer.resource == resource {
+                self.book_requests.push(BookRequest::AmendOffer(
+                    *offer_handle,
+                    offer.amount,
+                    price_per_unit,
+                ));
+            }
+        }
+    }
+
+    pub fn amend_order_amounts(
+        &mut self,
+        resource: ResourceHandle,
+        amount: f64,
+        market_data: &MarketData,
+    ) {
+        for order_handle in self.open_orders.iter() {
+            let order = &market_data.orders[order_handle];
+            if order.resource == resource {
+                self.book_requests.push(BookRequest::AmendOrder(
+                    *order_handle,
+                    amount,
+                    order.max_price_per_unit,
+                ));
+            }
+        }
+    }
+
+    pub fn amend_offer_amounts(
+        &mut self,
+        resource: ResourceHandle,
+        amount: f64,
+        market_data: &MarketData,
+    ) {
+        for offer_handle in self.open_offers.iter() {
+            let offer = &market_data.offers[offer_handle];
+            if offer.resource == resource {
+                self.book_requests.push(BookRequest::AmendOffer(
+                    *offer_handle,
+                    amount,
+                    offer.price_per_unit,
+                ));
+            }
+        }
     }
 
     pub fn place_order(&mut self, resource: ResourceHandle, amount: f64, max_price_per_unit: f64) {
+        self.place_order_with_type(resource, amount, max_price_per_unit, OrderType::Limit);
+    }
+
+    // Prices the order relative to the reference price, nothing is placed without one
+    pub fn place_relative_order(
+        &mut self,
+        resource: ResourceHandle,
+        amount: f64,
+        price_percentage: f64,
+        reference: PriceReference,
+        market_data: &MarketData,
+    ) {
+        if let Some(price) = market_data.get_reference_price(resource, reference) {
+            self.place_order(resource, amount, price * price_percentage / 100.0);
+        }
+    }
+
+    pub fn place_order_with_type(
+        &mut self,
+        resource: ResourceHandle,
+        amount: f64,
+        max_price_per_unit: f64,
+        order_type: OrderType,
+    ) {
         self.orders.push(UnprocessedOrder {
-            resource: resource,
-            amount: amount,
-            max_price_per_unit: max_price_per_unit,
+            resource,
+            amount,
+            max_price_per_unit,
             time_to_live: 100,
+            order_type,
         });
     }
 
     pub fn place_offer(&mut self, resource: ResourceHandle, amount: f64, price_per_unit: f64) {
+        self.place_offer_with_type(resource, amount, price_per_unit, OrderType::Limit);
+    }
+
+    pub fn place_relative_offer(
+        &mut self,
+        resource: ResourceHandle,
+        amount: f64,
+        price_percentage: f64,
+        reference: PriceReference,
+        market_data: &MarketData,
+    ) {
+        if let Some(price) = market_data.get_reference_price(resource, reference) {
+            self.place_offer(resource, amount, price * price_percentage / 100.0);
+        }
+    }
+
+    pub fn place_offer_with_type(
+        &mut self,
+        resource: ResourceHandle,
+        amount: f64,
+        price_per_unit: f64,
+        order_type: OrderType,
+    ) {
         self.offers.push(UnprocessedOffer {
-            resource: resource,
-            amount: amount,
-            price_per_unit: price_per_unit,
+            resource,
+            amount,
+            price_per_unit,
             time_to_live: 100,
+            order_type,
+        });
+    }
+
+    // Ships resources to another region and offers them there on arrival
+    pub fn export(
+        &mut self,
+        resource: ResourceHandle,
+        amount: f64,
+        price_per_unit: f64,
+        destination: RegionHandle,
+    ) {
+        if destination == self.region {
+            return;
+        }
+        self.exports.push(UnprocessedShipment {
+            destination,
+            offer: UnprocessedOffer {
+                resource,
+                amount,
+                price_per_unit,
+                time_to_live: 100,
+                order_type: OrderType::Limit,
+            },
         });
     }
 
-    pub fn calculate_company_value(&self, market_data: &MarketData, processor_value: f64) -> f64 {
-        let mut new_company_value = self.currency;
+    pub fn place_future(
+        &mut self,
+        position: Position,
+        resource: ResourceHandle,
+        amount: f64,
+        price_per_unit: f64,
+        maturity: usize,
+    ) {
+        self.futures.push(UnprocessedFuture {
+            position,
+            resource,
+            amount,
+            price_per_unit,
+            maturity,
+        });
+    }
+
+    // Stock and offers of each resource at market prices plus the currency escrowed for its
+    // orders
+    pub fn calculate_resource_values(&self, market_data: &MarketData) -> Vec<f64> {
+        let get_price = |resource: &ResourceHandle| match market_data.price_index.get(resource) {
+            Some(Some((_, price))) => *price,
+            _ => 0.0,
+        };
+        let mut values = vec![0.0; market_data.resource_count];
+        for (resource, amount) in self.stock.resources.iter() {
+            if let Some(value) = values.get_mut(*resource) {
+                *value += amount * get_price(resource);
+            }
+        }
+        for offer in self
+            .open_offers
+            .iter()
+            .filter_map(|offer_handle| market_data.offers.get(offer_handle))
+        {
+            if let Some(value) = values.get_mut(offer.resource) {
+                *value += offer.amount * get_price(&offer.resource);
+            }
+        }
+        for order in self
+            .open_orders
+            .iter()
+            .filter_map(|order_handle| market_data.orders.get(order_handle))
+        {
+            if let Some(value) = values.get_mut(order.resource) {
+                *value += order.amount * order.max_price_per_unit;
+            }
+        }
+        values
+    }
+
+    // Currency received minus currency paid per resource in the last market tick
+    fn get_resource_cash_flows(&self, market_data: &MarketData) -> Vec<f64> {
+        let mut cash_flows = vec![0.0; market_data.resource_count];
+        for record in market_data
+            .log
+            .trades
+            .iter()
+            .rev()
+            .take_while(|record| record.tick + 1 >= market_data.current_tick)
+        {
+            let cash_flow = match cash_flows.get_mut(record.trade.resource) {
+                Some(cash_flow) => cash_flow,
+                None => continue,
+            };
+            if record.trade.seller == Some(self.id) {
+                *cash_flow += record.trade.get_volume();
+            }
+            if record.trade.buyer == Some(self.id) {
+                *cash_flow -= record.trade.get_volume();
+            }
+        }
+        cash_flows
+    }
+
+    // Resource groups are credited with the value change of their holdings plus the cash flow
+    // of their trades, the rest of the reward is shared by the groups without a resource
+    fn get_group_rewards(
+        &self,
+        reward: f64,
+        resource_values: &[f64],
+        market_data: &MarketData,
+        actionspace: &ActionSpace,
+    ) -> Vec<f64> {
+        let group_resources = actionspace.get_group_resources();
+        let cash_flows = self.get_resource_cash_flows(market_data);
+        let mut group_rewards: Vec<f64> = group_resources
+            .iter()
+            .map(|resource| match resource {
+                Some(resource) => {
+                    let value = resource_values.get(*resource).copied().unwrap_or(0.0);
+                    let old_value = self
+                        .old_resource_values
+                        .get(*resource)
+                        .copied()
+                        .unwrap_or(value);
+                    value - old_value + cash_flows.get(*resource).copied().unwrap_or(0.0)
+                }
+                None => 0.0,
+            })
+            .collect();
+        let remaining_reward = reward - group_rewards.iter().fold(0.0, |a, b| a + b);
+        let shared_groups = group_resources
+            .iter()
+            .filter(|resource| resource.is_none())
+            .count()
+            .max(1);
+        for (group_reward, resource) in group_rewards.iter_mut().zip(group_resources.iter()) {
+            if resource.is_none() {
+                *group_reward = remaining_reward / shared_groups as f64;
+            }
+        }
+        group_rewards
+    }
+
+    // Listings and shipments in other regions are valued at the prices of the company's region
+    pub fn calculate_company_value(
+        &self,
+        home_market_data: &MarketData,
+        region_data: &RegionData,
+        processor_data: &ProcessorData,
+    ) -> f64 {
+        let market_data = region_data.get_market_data(self.region, home_market_data);
+        let get_value =
+            |resource: &ResourceHandle, amount: f64| match market_data.price_index.get(resource) {
+                Some(Some((_, price))) => amount * price,
+                _ => 0.0,
+            };
+        let mut new_company_value = self.currency + self.posted_margin;
         // Add value of all processors
-        new_company_value += self.processors.len() as f64 * processor_value;
+        for processor in self.processors.iter() {
+            new_company_value += processor_data.get_processor_price(processor.recipe);
+        }
+        // Add value of all processors offered on the market
+        for offer in market_data.processor_offers.values() {
+            if offer.company == self.id {
+                new_company_value += processor_data.get_processor_price(offer.recipe());
+            }
+        }
         // Add stockpile value
         for (resource, amount) in self.stock.resources.iter() {
-            if market_data.price_index.contains_key(resource) {
-                match market_data.price_index[resource] {
-                    Some((_, price)) => {
-                        new_company_value += *amount * price;
-                    }
-                    None => {
-                        continue;
-                    }
-                };
-            }
-        }
-        // Add companies offers current value
-        for offer in market_data.offers.values() {
-            match offer.company {
-                Some(company) => {
-                    if company != self.id {
-                        continue;
-                    }
-                }
-                None => {
-                    continue;
+            new_company_value += get_value(resource, *amount);
+        }
+        // Add companies offers current value on all markets
+        for listed_market_data in region_data.get_all_market_data(home_market_data) {
+            for offer in listed_market_data.offers.values() {
+                if offer.company == Some(self.id) {
+                    new_company_value += get_value(&offer.resource, offer.amount);
                 }
             }
-            match market_data.price_index[&offer.resource] {
-                Some((_, price)) => {
-                    new_company_value += offer.amount * price;
-                }
-                None => {
-                    break;
-                }
-            };
+        }
+        // Add resources in transit
+        for shipment in region_data.shipments.iter() {
+            if shipment.company == self.id {
+                new_company_value += get_value(&shipment.offer.resource, shipment.offer.amount);
+            }
         }
         new_company_value
     }
@@ -0,0 +1,60 @@
+use crate::economy::resource::ResourceHandle;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct CompanyStatistics {
+    // Storage
+    pub holding_costs: f64,
+    pub warehouse_spending: f64,
+    pub rejected_productions: usize,
+    pub forced_sale_amount: f64,
+    pub forced_sale_revenue: f64,
+    // Spoilage of stock and open offers
+    pub spoilage: HashMap<ResourceHandle, f64>,
+    // Labor
+    pub wages_paid: f64,
+    pub hours_worked: f64,
+    // Fiscal policy
+    pub taxes_paid: f64,
+    pub subsidies_received: f64,
+    pub tariffs_paid: f64,
+    // Listing fees, commissions, cancellation and expiry fees
+    pub market_fees: f64,
+    // Shipments to other regions
+    pub transport_costs: f64,
+    pub exported_amount: f64,
+    // Forward contracts and futures
+    pub settled_contracts: usize,
+    pub contract_penalties_paid: f64,
+    pub contract_penalties_received: f64,
+    // Supply contracts
+    pub supplied_amount: f64,
+    // Deliveries the company failed to make or to pay for
+    pub supply_breaches: usize,
+    // Agent decisions and how many of them would have been invalid without masking
+    pub chosen_actions: usize,
+    pub invalid_choices: usize,
+}
+
+impl CompanyStatistics {
+    pub fn new() -> CompanyStatistics {
+        CompanyStatistics::default()
+    }
+
+    pub fn add_spoilage(&mut self, resource: ResourceHandle, amount: f64) {
+        *self.spoilage.entry(resource).or_insert(0.0) += amount;
+    }
+
+    pub fn get_total_spoilage(&self) -> f64 {
+        self.spoilage.values().sum()
+    }
+
+    pub fn get_invalid_choice_rate(&self) -> f64 {
+        if self.chosen_actions == 0 {
+            return 0.0;
+        }
+        self.invalid_choices as f64 / self.chosen_actions as f64
+    }
+}
@@ -1,4 +1,5 @@
 use crate::market::order::UnprocessedOrder;
+use crate::world_data::region_data::RegionHandle;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -7,6 +8,14 @@ pub struct Consumer {
     pub orders: Vec<UnprocessedOrder>,
     pub order_creation_ticks: usize,
     pub current_tick: usize,
+    #[serde(default)]
+    pub region: RegionHandle,
+}
+
+impl Default for Consumer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Consumer {
@@ -16,6 +25,7 @@ impl Consumer {
             orders: vec![],
             order_creation_ticks: 1000,
             current_tick: 0,
+            region: 0,
         }
     }
 
@@ -0,0 +1,101 @@
+use crate::economy::company::CompanyHandle;
+use crate::economy::resource::ResourceHandle;
+use serde::{Deserialize, Serialize};
+
+pub type ContractHandle = usize;
+
+// Obligation of the seller to deliver a resource at a fixed price on the delivery tick. Both
+// parties post margin which is returned at settlement.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ForwardContract {
+    pub buyer: CompanyHandle,
+    pub seller: CompanyHandle,
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price_per_unit: f64,
+    pub delivery_tick: usize,
+    pub buyer_margin: f64,
+    pub seller_margin: f64,
+}
+
+impl ForwardContract {
+    pub fn get_volume(&self) -> f64 {
+        self.amount * self.price_per_unit
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum Position {
+    // Buys the resource at delivery
+    Long,
+    // Sells the resource at delivery
+    Short,
+}
+
+// Futures listing requested by a company, maturities count futures periods from now
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UnprocessedFuture {
+    pub position: Position,
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price_per_unit: f64,
+    pub maturity: usize,
+}
+
+// Recurring delivery of a fixed amount at a fixed price every interval ticks
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SupplyContract {
+    pub supplier: CompanyHandle,
+    pub customer: CompanyHandle,
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price_per_unit: f64,
+    pub interval: usize,
+    pub remaining_deliveries: usize,
+    pub next_delivery_tick: usize,
+    // Deliveries which were not fulfilled completely
+    pub breaches: usize,
+}
+
+// Open offer to enter a supply contract, the proposer takes the long side as customer or the
+// short side as supplier
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SupplyProposal {
+    pub proposer: CompanyHandle,
+    pub position: Position,
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price_per_unit: f64,
+    pub interval: usize,
+    pub periods: usize,
+    pub time_to_live: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub enum SupplyContractRequest {
+    Propose {
+        position: Position,
+        resource: ResourceHandle,
+        amount: f64,
+        price_per_unit: f64,
+        interval: usize,
+        periods: usize,
+    },
+    // Accepts the best proposal of the opposite position for the resource
+    Accept {
+        position: Position,
+        resource: ResourceHandle,
+    },
+}
+
+// Standardized futures listing on the futures book, matched listings become forward contracts
+#[derive(Serialize, Deserialize, Clone)]
+pub struct FutureListing {
+    pub company: CompanyHandle,
+    pub position: Position,
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price_per_unit: f64,
+    pub delivery_tick: usize,
+    pub time_to_live: usize,
+}
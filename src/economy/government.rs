@@ -0,0 +1,147 @@
+use crate::economy::company::Company;
+use crate::economy::recipe::RecipeHandle;
+use crate::world_data::processor_data::ProcessorData;
+use crate::world_data::region_data::RegionHandle;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize)]
+pub struct Government {
+    // Share of the trade volume a selling company pays on every market fill
+    pub vat_rate: f64,
+    // Share of company value gains above the previous high taxed every tax period
+    pub profit_tax_rate: f64,
+    // Share of the processor value taxed every tax period
+    pub property_tax_rate: f64,
+    // Subsidy paid per production cycle of a recipe
+    pub subsidies: HashMap<RecipeHandle, f64>,
+    // Share of the declared value of shipments leaving or entering a region
+    #[serde(default)]
+    pub export_tariffs: HashMap<RegionHandle, f64>,
+    #[serde(default)]
+    pub import_tariffs: HashMap<RegionHandle, f64>,
+    pub tax_period: usize,
+    pub current_tick: usize,
+    pub budget: f64,
+    pub vat_revenue: f64,
+    pub profit_tax_revenue: f64,
+    pub property_tax_revenue: f64,
+    pub subsidy_spending: f64,
+    #[serde(default)]
+    pub tariff_revenue: f64,
+}
+
+impl Default for Government {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Government {
+    pub fn new() -> Self {
+        Self {
+            vat_rate: 0.0,
+            profit_tax_rate: 0.0,
+            property_tax_rate: 0.0,
+            subsidies: HashMap::new(),
+            export_tariffs: HashMap::new(),
+            import_tariffs: HashMap::new(),
+            tax_period: 100,
+            current_tick: 0,
+            budget: 0.0,
+            vat_revenue: 0.0,
+            profit_tax_revenue: 0.0,
+            property_tax_revenue: 0.0,
+            subsidy_spending: 0.0,
+            tariff_revenue: 0.0,
+        }
+    }
+
+    pub fn collect_vat(&mut self, company: &mut Company, volume: f64) {
+        let vat = volume * self.vat_rate;
+        if vat <= 0.0 {
+            return;
+        }
+        company.currency -= vat;
+        company.statistics.taxes_paid += vat;
+        self.vat_revenue += vat;
+        self.budget += vat;
+    }
+
+    // Export tariff of the origin plus import tariff of the destination
+    pub fn get_tariff(&self, from: RegionHandle, to: RegionHandle, value: f64) -> f64 {
+        let export_rate = self.export_tariffs.get(&from).copied().unwrap_or(0.0);
+        let import_rate = self.import_tariffs.get(&to).copied().unwrap_or(0.0);
+        value * (export_rate + import_rate)
+    }
+
+    pub fn collect_tariff(&mut self, company: &mut Company, tariff: f64) {
+        if tariff <= 0.0 {
+            return;
+        }
+        company.currency -= tariff;
+        company.statistics.tariffs_paid += tariff;
+        self.tariff_revenue += tariff;
+        self.budget += tariff;
+    }
+
+    pub fn pay_subsidies(&mut self, company: &mut Company, productions: &[RecipeHandle]) {
+        for recipe in productions.iter() {
+            if let Some(subsidy) = self.subsidies.get(recipe) {
+                company.currency += subsidy;
+                company.statistics.subsidies_received += subsidy;
+                self.subsidy_spending += subsidy;
+                self.budget -= subsidy;
+            }
+        }
+    }
+
+    fn collect_profit_tax(&mut self, company: &mut Company) {
+        // Only gains above the highest value taxed so far are taxed again
+        let baseline = match company.profit_tax_baseline {
+            Some(baseline) => baseline,
+            None => {
+                company.profit_tax_baseline = Some(company.company_value);
+                return;
+            }
+        };
+        let gain = company.company_value - baseline;
+        if gain <= 0.0 {
+            return;
+        }
+        let tax = gain * self.profit_tax_rate;
+        company.currency -= tax;
+        company.statistics.taxes_paid += tax;
+        company.profit_tax_baseline = Some(company.company_value - tax);
+        self.profit_tax_revenue += tax;
+        self.budget += tax;
+    }
+
+    fn collect_property_tax(&mut self, company: &mut Company, processor_data: &ProcessorData) {
+        let processor_value: f64 = company
+            .processors
+            .iter()
+            .map(|processor| processor_data.get_processor_price(processor.recipe))
+            .sum();
+        let tax = processor_value * self.property_tax_rate;
+        if tax <= 0.0 {
+            return;
+        }
+        company.currency -= tax;
+        company.statistics.taxes_paid += tax;
+        self.property_tax_revenue += tax;
+        self.budget += tax;
+    }
+
+    pub fn tick(&mut self, companies: &mut [Company], processor_data: &ProcessorData) {
+        self.current_tick += 1;
+        if self.current_tick < self.tax_period {
+            return;
+        }
+        self.current_tick = 0;
+        for company in companies.iter_mut() {
+            self.collect_profit_tax(company);
+            self.collect_property_tax(company, processor_data);
+        }
+    }
+}
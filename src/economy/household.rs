@@ -0,0 +1,77 @@
+use crate::economy::resource::ResourceHandle;
+use crate::market::offer::UnprocessedOffer;
+use crate::market::order_type::OrderType;
+use crate::world_data::region_data::RegionHandle;
+use serde::{Deserialize, Serialize};
+
+pub type HouseholdHandle = usize;
+
+// A household supplies worker-hours to the labor market and adjusts its asking wage to the
+// share of offered hours companies actually bought
+#[derive(Serialize, Deserialize)]
+pub struct Household {
+    pub workers: f64,
+    pub reservation_wage: f64,
+    pub minimum_wage: f64,
+    pub wage_adjustment_rate: f64,
+    pub target_employment_rate: f64,
+    pub currency: f64,
+    pub offers: Vec<UnprocessedOffer>,
+    pub offered_hours: f64,
+    pub sold_hours: f64,
+    pub employment_rate: f64,
+    #[serde(default)]
+    pub region: RegionHandle,
+}
+
+impl Default for Household {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Household {
+    pub fn new() -> Self {
+        Self {
+            workers: 0.0,
+            reservation_wage: 1.0,
+            minimum_wage: 0.0,
+            wage_adjustment_rate: 0.01,
+            target_employment_rate: 0.95,
+            currency: 0.0,
+            offers: vec![],
+            offered_hours: 0.0,
+            sold_hours: 0.0,
+            employment_rate: 0.0,
+            region: 0,
+        }
+    }
+
+    pub fn tick(&mut self, labor_resource: ResourceHandle, hours_per_worker: f64) {
+        // Adjust asking wage according to last tick's employment
+        if self.offered_hours > 0.0 {
+            self.employment_rate = self.sold_hours / self.offered_hours;
+            if self.employment_rate >= self.target_employment_rate {
+                self.reservation_wage *= 1.0 + self.wage_adjustment_rate;
+            } else {
+                self.reservation_wage = (self.reservation_wage * (1.0 - self.wage_adjustment_rate))
+                    .max(self.minimum_wage);
+            }
+        }
+        self.offered_hours = self.workers * hours_per_worker;
+        self.sold_hours = 0.0;
+        // Worker-hours can not be stored and are only offered for a single tick
+        self.offers.push(UnprocessedOffer {
+            resource: labor_resource,
+            amount: self.offered_hours,
+            price_per_unit: self.reservation_wage,
+            time_to_live: 1,
+            order_type: OrderType::Limit,
+        });
+    }
+
+    pub fn receive_wage(&mut self, hours: f64, wage: f64) {
+        self.sold_hours += hours;
+        self.currency += wage;
+    }
+}
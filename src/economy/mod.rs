@@ -1,5 +1,9 @@
 pub mod company;
+pub mod company_statistics;
 pub mod consumer;
+pub mod contract;
+pub mod government;
+pub mod household;
 pub mod processor;
 pub mod producer;
 pub mod recipe;
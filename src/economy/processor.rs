@@ -1,4 +1,5 @@
 use crate::economy::recipe::RecipeHandle;
+use crate::economy::resource::ResourceHandle;
 use crate::economy::stock::Stock;
 use crate::world_data::recipe_data::RecipeData;
 use serde::{Deserialize, Serialize};
@@ -11,18 +12,22 @@ pub struct Processor {
     pub productive: bool,
 }
 
-impl<'a> Processor {
-    pub fn tick(&self, stock: &mut Stock, recipe_data: &RecipeData) {
+impl Processor {
+    pub fn tick(&self, stock: &mut Stock, recipe_data: &RecipeData) -> bool {
         // Get recipe
         let recipe = recipe_data.get_recipe_by_handle(self.recipe).unwrap();
         // Check if transaction can be done
-        let transaction = &recipe.ingredients.iter().map(|x| (*x.0, *x.1)).collect();
-        if self.productive && stock.make_transaction(transaction) {
+        let transaction: Vec<(ResourceHandle, f64)> =
+            recipe.ingredients.iter().map(|x| (*x.0, *x.1)).collect();
+        if self.productive && stock.make_transaction(&transaction) {
             // Transaction can be done, add generated resources to stock
             for (resource, production_factor) in recipe.products.iter() {
                 let amount = production_factor * self.production_speed;
                 stock.add_to_stock(*resource, amount);
             }
+            true
+        } else {
+            false
         }
     }
 }
@@ -1,5 +1,6 @@
 use crate::market::offer::UnprocessedOffer;
 
+use crate::world_data::region_data::RegionHandle;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -8,6 +9,14 @@ pub struct Producer {
     pub offers: Vec<UnprocessedOffer>,
     pub offer_creation_ticks: usize,
     pub current_tick: usize,
+    #[serde(default)]
+    pub region: RegionHandle,
+}
+
+impl Default for Producer {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Producer {
@@ -17,6 +26,7 @@ impl Producer {
             offers: vec![],
             offer_creation_ticks: 1000,
             current_tick: 0,
+            region: 0,
         }
     }
 
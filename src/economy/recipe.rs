@@ -15,10 +15,10 @@ pub struct Recipe {
 impl Recipe {
     pub fn new(name: String, production_speed: f64) -> Self {
         Self {
-            name: name,
+            name,
             ingredients: HashMap::new(),
             products: HashMap::new(),
-            production_speed: production_speed,
+            production_speed,
         }
     }
 }
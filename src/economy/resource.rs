@@ -1,9 +1,44 @@
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::usize;
 
 pub type ResourceHandle = usize;
 
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ResourceUnit {
+    // Resource is handled in whole units only
+    Discrete,
+    #[default]
+    Continuous,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Resource {
     pub name: String,
+    #[serde(default)]
+    pub category: Option<String>,
+    // Fraction of the resource lost each tick
+    #[serde(default)]
+    pub decay_rate: f64,
+    #[serde(default)]
+    pub unit: ResourceUnit,
+}
+
+impl Resource {
+    pub fn get_decay<R: Rng>(&self, amount: f64, rng: &mut R) -> f64 {
+        if self.decay_rate <= 0.0 || amount <= 0.0 {
+            return 0.0;
+        }
+        let decay = (amount * self.decay_rate).min(amount);
+        match self.unit {
+            ResourceUnit::Continuous => decay,
+            ResourceUnit::Discrete => {
+                // Round stochastically so small stocks still decay on average
+                let mut whole_units = decay.floor();
+                if rng.gen::<f64>() < decay - whole_units {
+                    whole_units += 1.0;
+                }
+                whole_units.min(amount.floor())
+            }
+        }
+    }
 }
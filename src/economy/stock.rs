@@ -1,6 +1,7 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use log::info;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::economy::resource::ResourceHandle;
@@ -9,16 +10,27 @@ use crate::world_data::resource_data::ResourceData;
 #[derive(Serialize, Deserialize, Clone)]
 
 pub struct Stock {
-    pub resources: HashMap<ResourceHandle, f64>,
+    // Ordered, so sums and random draws over the stock are the same every run
+    pub resources: BTreeMap<ResourceHandle, f64>,
+}
+
+impl Default for Stock {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Stock {
     pub fn new() -> Self {
         Self {
-            resources: HashMap::new(),
+            resources: BTreeMap::new(),
         }
     }
 
+    pub fn get_amount(&self, resource: ResourceHandle) -> f64 {
+        self.resources.get(&resource).copied().unwrap_or(0.0)
+    }
+
     fn get_resource_amount_in_stock(&mut self, resource: ResourceHandle) -> f64 {
         match self.resources.get(&resource) {
             Some(value) => *value,
@@ -40,7 +52,7 @@ impl Stock {
 
     pub fn check_resources_in_stock(
         &mut self,
-        resource_transactions: &Vec<(ResourceHandle, f64)>,
+        resource_transactions: &[(ResourceHandle, f64)],
     ) -> bool {
         let mut in_stock: bool = true;
         for (resource, amount) in resource_transactions.iter() {
@@ -49,7 +61,7 @@ impl Stock {
         in_stock
     }
 
-    pub fn make_transaction(&mut self, resource_transactions: &Vec<(ResourceHandle, f64)>) -> bool {
+    pub fn make_transaction(&mut self, resource_transactions: &[(ResourceHandle, f64)]) -> bool {
         if self.check_resources_in_stock(resource_transactions) {
             for (resource, amount) in resource_transactions.iter() {
                 self.remove_from_stock_if_possible(*resource, *amount);
@@ -75,13 +87,29 @@ impl Stock {
         self.resources.insert(resource, new_value);
     }
 
+    pub fn apply_decay<R: Rng>(
+        &mut self,
+        resource_data: &ResourceData,
+        rng: &mut R,
+    ) -> HashMap<ResourceHandle, f64> {
+        let mut losses: HashMap<ResourceHandle, f64> = HashMap::new();
+        for (resource_handle, amount) in self.resources.iter_mut() {
+            if let Some(resource) = resource_data.resources.get(*resource_handle) {
+                let decay = resource.get_decay(*amount, rng);
+                if decay > 0.0 {
+                    *amount -= decay;
+                    losses.insert(*resource_handle, decay);
+                }
+            }
+        }
+        losses
+    }
+
     pub fn print_stock(&self, resource_data: &ResourceData) {
         for (&resource_handle, amount) in self.resources.iter() {
-            match resource_data.get_resource_name_by_handle(resource_handle) {
-                Some(resource_name) => {
-                    info!("Resource {}: {}", resource_name, amount);
-                }
-                None => {}
+            if let Some(resource_name) = resource_data.get_resource_name_by_handle(resource_handle)
+            {
+                info!("Resource {}: {}", resource_name, amount);
             }
         }
     }
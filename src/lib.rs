@@ -1,3 +1,4 @@
+pub mod analysis;
 pub mod economy;
 pub mod market;
 pub mod persistence;
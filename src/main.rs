@@ -1,6 +1,10 @@
-use clap::{arg, command, Parser};
+use clap::{Parser, Subcommand};
+use econo_sim::analysis::evaluation::{run_episode, swap_agents, EvaluationReport};
+use econo_sim::analysis::manipulation::{ManipulationReport, ManipulationThresholds};
 use econo_sim::persistence::Persistence;
 use format_num::NumberFormat;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 use simple_logger::SimpleLogger;
 use std::time::Instant;
 
@@ -13,17 +17,75 @@ struct Args {
     /// Path to save trained world to
     #[arg(short, long, default_value_t = String::from("data/trained_world.yml"))]
     out_file: String,
+    /// Trains the agents if not given
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run the trained agents without learning on fresh copies of the loaded world
+    Evaluate {
+        /// Path to load the trained agents from
+        #[arg(short, long, default_value_t = String::from("data/trained_world.yml"))]
+        trained_file: String,
+        /// Number of episodes per seed
+        #[arg(short, long, default_value_t = 10)]
+        episodes: usize,
+        /// Number of ticks per episode
+        #[arg(long, default_value_t = 1000)]
+        ticks: usize,
+        /// Seeds of the world's random number generator
+        #[arg(short, long, value_delimiter = ',', default_value = "0,1,2,3,4")]
+        seeds: Vec<u64>,
+    },
 }
 
 fn main() {
     let cli_args = Args::parse();
     SimpleLogger::new().init().unwrap();
+    match cli_args.command {
+        Some(Command::Evaluate {
+            trained_file,
+            episodes,
+            ticks,
+            seeds,
+        }) => evaluate(&cli_args.in_file, &trained_file, episodes, ticks, &seeds),
+        None => train(&cli_args.in_file, &cli_args.out_file),
+    }
+}
+
+fn evaluate(in_file: &str, trained_file: &str, episodes: usize, ticks: usize, seeds: &[u64]) {
+    log::info!("=== EVALUATION ===");
+    let mut trained_world = Persistence::load_world_from(trained_file);
+    let mut report = EvaluationReport::new();
+    for seed in seeds.iter() {
+        // Episodes of a seed get their own seeds from a generator seeded with it
+        let mut rng = StdRng::seed_from_u64(*seed);
+        for episode in 0..episodes {
+            let mut world = Persistence::load_world_from(in_file);
+            swap_agents(&mut world, &mut trained_world);
+            let result = run_episode(&mut world, rng.gen(), ticks);
+            swap_agents(&mut world, &mut trained_world);
+            log::info!(
+                "Seed {} episode {}: company values {:.2?}",
+                seed,
+                episode,
+                result.company_values
+            );
+            report.episodes.push(result);
+        }
+    }
+    report.print(&trained_world.company_data);
+}
+
+fn train(in_file: &str, out_file: &str) {
     log::info!("=== SIM TEST ===");
     let num = NumberFormat::new();
     let epochs = 100000;
     // Load world
-    let prestine_world = Persistence::load_world_from(&cli_args.in_file);
-    let mut trained_world = Persistence::load_world_from(&cli_args.in_file);
+    let prestine_world = Persistence::load_world_from(in_file);
+    let mut trained_world = Persistence::load_world_from(in_file);
     let mut old_company_values: Vec<f64> = trained_world
         .company_data
         .companies
@@ -54,7 +116,15 @@ fn main() {
             company.currency = reference_company.currency;
             company.company_value = reference_company.company_value;
             company.processors = reference_company.processors.clone();
+            company.warehouses = reference_company.warehouses;
+            company.statistics = reference_company.statistics.clone();
+            company.employees = reference_company.employees;
+            company.wage = reference_company.wage;
+            company.profit_tax_baseline = reference_company.profit_tax_baseline;
+            company.posted_margin = reference_company.posted_margin;
         }
+        // Open contracts hold the margins reset above
+        trained_world.contract_data = prestine_world.contract_data.clone();
         let mut start = Instant::now();
         for k in 0..epoch + 1 {
             if k % 1000 == 0 {
@@ -67,7 +137,7 @@ fn main() {
         if epoch % 10 == 0 {
             log::info!("Simulating...");
             start = Instant::now();
-            for k in 0..epoch + 1 {
+            for _k in 0..epoch + 1 {
                 trained_world.tick(false, 0.0);
             }
             fps = num.format(".4s", (epoch + 1) as f64 / start.elapsed().as_secs_f64());
@@ -77,16 +147,20 @@ fn main() {
                 let delta = num.format(".4s", company.company_value - old_company_values[i]);
                 let max_delta = num.format(".4s", company.company_value - max_company_values[i]);
                 log::info!(
-                    "- {}:\t{}\t({delta})\t[{max_delta}]",
+                    "- {} ({}):\t{}\t({delta})\t[{max_delta}]\tinvalid choices: {:.1}%",
                     company.name,
-                    num.format(".4s", company.company_value)
+                    company.agent.get_name(),
+                    num.format(".4s", company.company_value),
+                    company.statistics.get_invalid_choice_rate() * 100.0
                 );
                 old_company_values[i] = company.company_value;
                 if max_company_values[i] < company.company_value {
                     max_company_values[i] = company.company_value;
                 }
             }
+            ManipulationReport::from_world(&trained_world, ManipulationThresholds::default())
+                .print(&trained_world.company_data, &trained_world.resource_data);
         }
-        Persistence::write_world_to(&trained_world, &cli_args.out_file);
+        Persistence::write_world_to(&trained_world, out_file);
     }
 }
@@ -0,0 +1,101 @@
+use crate::economy::resource::ResourceHandle;
+use std::cmp::Ordering;
+use std::collections::{BTreeSet, HashMap};
+
+// Total order over prices so they can be used as keys
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct Price(pub f64);
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+// Listings of one side of the book sorted per resource from the best to the worst price, equal
+// prices are sorted by handle and therefore by the time they were placed
+pub struct BookIndex {
+    highest_first: bool,
+    levels: HashMap<ResourceHandle, BTreeSet<(Price, usize)>>,
+    keys: HashMap<usize, (ResourceHandle, Price)>,
+}
+
+impl BookIndex {
+    // Offers, the cheapest first
+    pub fn asks() -> BookIndex {
+        BookIndex {
+            highest_first: false,
+            levels: HashMap::new(),
+            keys: HashMap::new(),
+        }
+    }
+
+    // Orders, the highest bid first
+    pub fn bids() -> BookIndex {
+        BookIndex {
+            highest_first: true,
+            levels: HashMap::new(),
+            keys: HashMap::new(),
+        }
+    }
+
+    fn get_key(&self, price: f64) -> Price {
+        match self.highest_first {
+            true => Price(-price),
+            false => Price(price),
+        }
+    }
+
+    // Inserts a listing or moves it to its new price
+    pub fn insert(&mut self, handle: usize, resource: ResourceHandle, price: f64) {
+        self.remove(handle);
+        let key = self.get_key(price);
+        self.levels
+            .entry(resource)
+            .or_default()
+            .insert((key, handle));
+        self.keys.insert(handle, (resource, key));
+    }
+
+    pub fn remove(&mut self, handle: usize) {
+        if let Some((resource, key)) = self.keys.remove(&handle) {
+            if let Some(level) = self.levels.get_mut(&resource) {
+                level.remove(&(key, handle));
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.levels.clear();
+        self.keys.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    // Handles and prices of all listings of a resource, the best price first
+    pub fn iter(&self, resource: ResourceHandle) -> impl Iterator<Item = (usize, f64)> + '_ {
+        let highest_first = self.highest_first;
+        self.levels
+            .get(&resource)
+            .into_iter()
+            .flat_map(|level| level.iter())
+            .map(move |(key, handle)| match highest_first {
+                true => (*handle, -key.0),
+                false => (*handle, key.0),
+            })
+    }
+}
@@ -0,0 +1,14 @@
+use crate::market::offer::OfferHandle;
+use crate::market::order::OrderHandle;
+use serde::{Deserialize, Serialize};
+
+// Changes a company requests for its open listings on the market
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug)]
+pub enum BookRequest {
+    CancelOrder(OrderHandle),
+    CancelOffer(OfferHandle),
+    // Order handle, new amount and new max price per unit
+    AmendOrder(OrderHandle, f64, f64),
+    // Offer handle, new amount and new price per unit
+    AmendOffer(OfferHandle, f64, f64),
+}
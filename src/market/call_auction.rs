@@ -0,0 +1,257 @@
+use crate::market::offer::OfferHandle;
+use crate::market::order::OrderHandle;
+use std::cmp::Ordering;
+
+pub struct AuctionBid {
+    pub order: OrderHandle,
+    // Market orders bid at any price
+    pub limit: f64,
+    pub amount: f64,
+    // Market orders can not spend more than their budget
+    pub budget: Option<f64>,
+    pub all_or_nothing: bool,
+}
+
+pub struct AuctionAsk {
+    pub offer: OfferHandle,
+    // Market offers ask for no minimum price, their limit is negative infinity
+    pub limit: f64,
+    pub amount: f64,
+    pub all_or_nothing: bool,
+}
+
+pub struct AuctionFill {
+    pub order: OrderHandle,
+    pub offer: OfferHandle,
+    pub amount: f64,
+}
+
+pub struct AuctionResult {
+    pub price: f64,
+    pub fills: Vec<AuctionFill>,
+}
+
+impl AuctionBid {
+    pub fn get_demand(&self, price: f64) -> f64 {
+        if self.limit < price {
+            return 0.0;
+        }
+        match self.budget {
+            Some(budget) if price > 0.0 => self.amount.min(budget / price),
+            _ => self.amount,
+        }
+    }
+}
+
+impl AuctionAsk {
+    pub fn get_supply(&self, price: f64) -> f64 {
+        if self.limit > price {
+            return 0.0;
+        }
+        self.amount
+    }
+}
+
+// Market listings don't quote a price, a book of only market listings clears at the reference
+// price or not at all
+fn get_clearing_price(
+    bids: &[AuctionBid],
+    asks: &[AuctionAsk],
+    reference_price: Option<f64>,
+) -> Option<f64> {
+    let mut candidates: Vec<f64> = bids
+        .iter()
+        .map(|bid| bid.limit)
+        .chain(asks.iter().map(|ask| ask.limit))
+        .filter(|price| price.is_finite())
+        .collect();
+    if candidates.is_empty() {
+        candidates.extend(reference_price);
+    }
+    candidates.sort_by(|a, b| a.total_cmp(b));
+    candidates.dedup();
+    // Maximize traded volume, then minimize the imbalance between demand and supply
+    let mut best_candidates: Vec<f64> = vec![];
+    let mut best_volume = 0.0;
+    let mut best_imbalance = f64::INFINITY;
+    for price in candidates {
+        let demand: f64 = bids.iter().map(|bid| bid.get_demand(price)).sum();
+        let supply: f64 = asks.iter().map(|ask| ask.get_supply(price)).sum();
+        let volume = demand.min(supply);
+        let imbalance = (demand - supply).abs();
+        if volume <= 0.0 {
+            continue;
+        }
+        match volume
+            .total_cmp(&best_volume)
+            .then(best_imbalance.total_cmp(&imbalance))
+        {
+            Ordering::Greater => {
+                best_candidates = vec![price];
+                best_volume = volume;
+                best_imbalance = imbalance;
+            }
+            Ordering::Equal => best_candidates.push(price),
+            Ordering::Less => {}
+        }
+    }
+    // Take the median of all equally good prices
+    if best_candidates.is_empty() {
+        None
+    } else {
+        Some(best_candidates[best_candidates.len() / 2])
+    }
+}
+
+fn allocate(price: f64, bids: &[AuctionBid], asks: &[AuctionAsk]) -> Vec<AuctionFill> {
+    // Better prices are served first, equal prices in the order they were placed
+    let mut eligible_bids: Vec<(OrderHandle, f64, f64)> = bids
+        .iter()
+        .map(|bid| (bid.order, bid.limit, bid.get_demand(price)))
+        .filter(|(_, _, demand)| *demand > 0.0)
+        .collect();
+    eligible_bids.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+    let mut eligible_asks: Vec<(OfferHandle, f64, f64)> = asks
+        .iter()
+        .map(|ask| (ask.offer, ask.limit, ask.get_supply(price)))
+        .filter(|(_, _, supply)| *supply > 0.0)
+        .collect();
+    eligible_asks.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
+    let mut fills: Vec<AuctionFill> = vec![];
+    let mut bid_index = 0;
+    let mut ask_index = 0;
+    while bid_index < eligible_bids.len() && ask_index < eligible_asks.len() {
+        let amount = eligible_bids[bid_index].2.min(eligible_asks[ask_index].2);
+        fills.push(AuctionFill {
+            order: eligible_bids[bid_index].0,
+            offer: eligible_asks[ask_index].0,
+            amount,
+        });
+        eligible_bids[bid_index].2 -= amount;
+        eligible_asks[ask_index].2 -= amount;
+        if eligible_bids[bid_index].2 <= 0.0 {
+            bid_index += 1;
+        }
+        if eligible_asks[ask_index].2 <= 0.0 {
+            ask_index += 1;
+        }
+    }
+    fills
+}
+
+// Clears all bids and asks of a single resource at one uniform price maximizing the traded
+// volume. All-or-nothing listings which would only be filled partially are excluded.
+pub fn clear_call_auction(
+    mut bids: Vec<AuctionBid>,
+    mut asks: Vec<AuctionAsk>,
+    reference_price: Option<f64>,
+) -> Option<AuctionResult> {
+    loop {
+        let price = get_clearing_price(&bids, &asks, reference_price)?;
+        let fills = allocate(price, &bids, &asks);
+        let bid_count = bids.len();
+        let ask_count = asks.len();
+        bids.retain(|bid| {
+            let filled: f64 = fills
+                .iter()
+                .filter(|fill| fill.order == bid.order)
+                .map(|fill| fill.amount)
+                .sum();
+            !bid.all_or_nothing || filled <= 0.0 || filled >= bid.get_demand(price)
+        });
+        asks.retain(|ask| {
+            let filled: f64 = fills
+                .iter()
+                .filter(|fill| fill.offer == ask.offer)
+                .map(|fill| fill.amount)
+                .sum();
+            !ask.all_or_nothing || filled <= 0.0 || filled >= ask.amount
+        });
+        if bids.len() == bid_count && asks.len() == ask_count {
+            return Some(AuctionResult { price, fills });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bid(order: OrderHandle, limit: f64, amount: f64, all_or_nothing: bool) -> AuctionBid {
+        AuctionBid {
+            order,
+            limit,
+            amount,
+            budget: None,
+            all_or_nothing,
+        }
+    }
+
+    fn ask(offer: OfferHandle, limit: f64, amount: f64, all_or_nothing: bool) -> AuctionAsk {
+        AuctionAsk {
+            offer,
+            limit,
+            amount,
+            all_or_nothing,
+        }
+    }
+
+    fn get_filled_amount(result: &AuctionResult, order: OrderHandle) -> f64 {
+        result
+            .fills
+            .iter()
+            .filter(|fill| fill.order == order)
+            .fold(0.0, |total, fill| total + fill.amount)
+    }
+
+    // Demand and supply meet at 8, where all 10 units trade at one price
+    #[test]
+    fn clears_at_volume_maximizing_uniform_price() {
+        let bids = vec![bid(1, 10.0, 6.0, false), bid(2, 8.0, 4.0, false)];
+        let asks = vec![ask(1, 6.0, 5.0, false), ask(2, 8.0, 5.0, false)];
+        let result = clear_call_auction(bids, asks, None).unwrap();
+        assert_eq!(result.price, 8.0);
+        assert_eq!(get_filled_amount(&result, 1), 6.0);
+        assert_eq!(get_filled_amount(&result, 2), 4.0);
+    }
+
+    // All prices trade 4 units, 5 and 7 leave the smaller imbalance and 7 is their median
+    #[test]
+    fn breaks_ties_by_imbalance_and_median() {
+        let bids = vec![bid(1, 10.0, 4.0, false), bid(2, 7.0, 2.0, false)];
+        let asks = vec![ask(1, 5.0, 4.0, false), ask(2, 9.0, 4.0, false)];
+        let result = clear_call_auction(bids, asks, None).unwrap();
+        assert_eq!(result.price, 7.0);
+        assert_eq!(get_filled_amount(&result, 1), 4.0);
+    }
+
+    #[test]
+    fn excludes_partially_filled_fill_or_kill_bid() {
+        let bids = vec![bid(1, 10.0, 3.0, true), bid(2, 9.0, 2.0, false)];
+        let asks = vec![ask(1, 8.0, 2.0, false)];
+        let result = clear_call_auction(bids, asks, None).unwrap();
+        assert_eq!(result.price, 9.0);
+        assert_eq!(get_filled_amount(&result, 1), 0.0);
+        assert_eq!(get_filled_amount(&result, 2), 2.0);
+    }
+
+    #[test]
+    fn excludes_partially_filled_fill_or_kill_ask() {
+        let bids = vec![bid(1, 10.0, 3.0, false)];
+        let asks = vec![ask(1, 8.0, 5.0, true), ask(2, 9.0, 2.0, false)];
+        let result = clear_call_auction(bids, asks, None).unwrap();
+        assert_eq!(result.price, 10.0);
+        assert!(result.fills.iter().all(|fill| fill.offer == 2));
+        assert_eq!(get_filled_amount(&result, 1), 2.0);
+    }
+
+    #[test]
+    fn clears_market_only_book_at_reference_price() {
+        let bids = || vec![bid(1, f64::INFINITY, 2.0, false)];
+        let asks = || vec![ask(1, f64::NEG_INFINITY, 2.0, false)];
+        let result = clear_call_auction(bids(), asks(), Some(5.0)).unwrap();
+        assert_eq!(result.price, 5.0);
+        assert_eq!(get_filled_amount(&result, 1), 2.0);
+        assert!(clear_call_auction(bids(), asks(), None).is_none());
+    }
+}
@@ -0,0 +1,64 @@
+use crate::market::trade::Trade;
+use serde::{Deserialize, Serialize};
+
+// Market statistics of a single resource over one tick
+#[derive(Serialize, Deserialize, Clone, Default, Debug)]
+pub struct Candle {
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    // Traded amount
+    pub volume: f64,
+    // Traded currency
+    pub turnover: f64,
+    pub trade_count: usize,
+    pub best_bid: Option<f64>,
+    pub best_ask: Option<f64>,
+    // Resting amounts on each side of the book after the tick
+    pub bid_depth: f64,
+    pub ask_depth: f64,
+}
+
+impl Candle {
+    // Candles without trades keep the previous close as open, high, low and close
+    pub fn new(previous_close: f64, trades: &[&Trade]) -> Candle {
+        let mut candle = Candle {
+            open: previous_close,
+            high: previous_close,
+            low: previous_close,
+            close: previous_close,
+            ..Default::default()
+        };
+        if let Some(first_trade) = trades.first() {
+            candle.open = first_trade.price_per_unit;
+            candle.high = first_trade.price_per_unit;
+            candle.low = first_trade.price_per_unit;
+        }
+        for trade in trades {
+            candle.high = candle.high.max(trade.price_per_unit);
+            candle.low = candle.low.min(trade.price_per_unit);
+            candle.close = trade.price_per_unit;
+            candle.volume += trade.amount;
+            candle.turnover += trade.get_volume();
+            candle.trade_count += 1;
+        }
+        candle
+    }
+
+    // Volume weighted average price, the close if nothing was traded
+    pub fn get_vwap(&self) -> f64 {
+        if self.volume > 0.0 {
+            self.turnover / self.volume
+        } else {
+            self.close
+        }
+    }
+
+    pub fn get_spread(&self) -> Option<f64> {
+        match (self.best_bid, self.best_ask) {
+            (Some(bid), Some(ask)) => Some(ask - bid),
+            _ => None,
+        }
+    }
+}
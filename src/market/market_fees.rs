@@ -0,0 +1,24 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct MarketFees {
+    // Fees charged when placing a listing
+    pub offer_listing_fee: f64,
+    pub order_listing_fee: f64,
+    // Commission charged to both trading companies on every fill
+    pub flat_commission: f64,
+    pub commission_rate: f64,
+    // Fees charged when a listing is cancelled or expires unfilled
+    pub cancellation_fee: f64,
+    pub expiry_fee: f64,
+}
+
+impl MarketFees {
+    pub fn new() -> MarketFees {
+        MarketFees::default()
+    }
+
+    pub fn get_commission(&self, volume: f64) -> f64 {
+        self.flat_commission + volume * self.commission_rate
+    }
+}
@@ -0,0 +1,89 @@
+use crate::economy::company::CompanyHandle;
+use crate::economy::resource::ResourceHandle;
+use crate::market::trade::Trade;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::VecDeque;
+
+// Entries of each kind saved with the world, older entries only live for the current run
+const PERSISTED_ENTRIES: usize = 1000;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ListingSide {
+    Offer,
+    Order,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ListingEventKind {
+    Placed,
+    Cancelled,
+    Expired,
+}
+
+// Amount is the listed amount when placed and the unfilled remainder when removed
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ListingEvent {
+    pub tick: usize,
+    pub side: ListingSide,
+    pub kind: ListingEventKind,
+    pub handle: usize,
+    pub company: Option<CompanyHandle>,
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price_per_unit: f64,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TradeRecord {
+    pub tick: usize,
+    pub trade: Trade,
+}
+
+// Bounded history of listing events and trades, the oldest entries are dropped first
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MarketLog {
+    #[serde(serialize_with = "serialize_newest")]
+    pub listing_events: VecDeque<ListingEvent>,
+    #[serde(serialize_with = "serialize_newest")]
+    pub trades: VecDeque<TradeRecord>,
+    pub capacity: usize,
+}
+
+fn serialize_newest<S: Serializer, T: Serialize>(
+    entries: &VecDeque<T>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let skipped = entries.len().saturating_sub(PERSISTED_ENTRIES);
+    serializer.collect_seq(entries.iter().skip(skipped))
+}
+
+impl Default for MarketLog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MarketLog {
+    pub fn new() -> MarketLog {
+        MarketLog {
+            listing_events: VecDeque::new(),
+            trades: VecDeque::new(),
+            capacity: 100000,
+        }
+    }
+
+    pub fn record_listing_event(&mut self, event: ListingEvent) {
+        if self.listing_events.len() >= self.capacity {
+            self.listing_events.pop_front();
+        }
+        self.listing_events.push_back(event);
+    }
+
+    pub fn record_trade(&mut self, tick: usize, trade: Trade) {
+        if self.trades.len() >= self.capacity {
+            self.trades.pop_front();
+        }
+        self.trades.push_back(TradeRecord { tick, trade });
+    }
+}
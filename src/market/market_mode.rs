@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum MarketMode {
+    // Orders are matched one by one against the best offers in the order they are stored
+    #[default]
+    Continuous,
+    // All bids and asks of a resource are cleared once per tick at a single uniform price
+    CallAuction,
+}
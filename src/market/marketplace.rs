@@ -1,17 +1,40 @@
-use crate::economy::company::Company;
+use crate::economy::company::{Company, CompanyHandle};
+use crate::economy::government::Government;
+use crate::economy::household::Household;
 use crate::economy::resource::ResourceHandle;
+use crate::market::call_auction::{clear_call_auction, AuctionAsk, AuctionBid};
+use crate::market::market_fees::MarketFees;
+use crate::market::market_log::ListingEventKind;
+use crate::market::market_mode::MarketMode;
 use crate::market::offer::Offer;
 use crate::market::offer::OfferHandle;
 use crate::market::order::Order;
 use crate::market::order::OrderHandle;
+use crate::market::order_type::OrderType;
+use crate::market::price_reference::PriceReference;
+use crate::market::processor_offer::{ProcessorOffer, ProcessorOfferHandle, ProcessorOrder};
+use crate::market::trade::Trade;
 use crate::world_data::market_data::MarketData;
+use crate::world_data::resource_data::ResourceData;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Default)]
 pub struct Marketplace {
     next_offer_id: OfferHandle,
     next_order_id: OrderHandle,
+    #[serde(default)]
+    next_processor_offer_id: ProcessorOfferHandle,
+    #[serde(default)]
+    pub market_mode: MarketMode,
+    #[serde(default)]
+    pub fees: MarketFees,
+    // All fees are collected by the market operator
+    #[serde(default)]
+    pub operator_account: f64,
+    // Unsold resources of removed company offers, the world returns them to the companies
+    #[serde(skip)]
+    pub returned_offers: Vec<Offer>,
 }
 
 impl Marketplace {
@@ -19,56 +42,41 @@ impl Marketplace {
         Marketplace {
             next_offer_id: 0,
             next_order_id: 0,
+            next_processor_offer_id: 0,
+            market_mode: MarketMode::Continuous,
+            fees: MarketFees::new(),
+            operator_account: 0.0,
+            returned_offers: vec![],
         }
     }
 
+    // Indexes are maintained incrementally, this refreshes them from the books
     pub fn update_price_index(&self, market_data: &mut MarketData) {
         for resource_handle in 0..market_data.resource_count {
-            let offer = self.get_cheapest_offer(resource_handle, &market_data.offers);
-            market_data.price_index.insert(resource_handle, offer);
+            market_data.update_best_offer(resource_handle);
         }
     }
 
     pub fn update_order_index(&self, market_data: &mut MarketData) {
         for resource_handle in 0..market_data.resource_count {
-            let order = self.get_highest_order(resource_handle, market_data);
-            market_data.order_index.insert(resource_handle, order);
+            market_data.update_best_order(resource_handle);
         }
     }
 
     pub fn get_cheapest_offer(
         &self,
         resource: ResourceHandle,
-        offers: &HashMap<OfferHandle, Offer>,
+        market_data: &MarketData,
     ) -> Option<(OfferHandle, f64)> {
-        let mut cheapest_offer: Option<(OfferHandle, f64)> = None;
-        for (offer_handle, offer) in offers.iter() {
-            if offer.resource == resource {
-                if cheapest_offer.is_none() {
-                    cheapest_offer = Some((*offer_handle, offer.price_per_unit));
-                } else if cheapest_offer.unwrap().1 > offer.price_per_unit {
-                    cheapest_offer = Some((*offer_handle, offer.price_per_unit));
-                }
-            }
-        }
-        cheapest_offer
+        market_data.price_index.get(&resource).copied().flatten()
     }
+
     pub fn get_highest_order(
         &self,
         resource: ResourceHandle,
-        market_data: &mut MarketData,
+        market_data: &MarketData,
     ) -> Option<(OrderHandle, f64)> {
-        let mut highest_order: Option<(OrderHandle, f64)> = None;
-        for (order_handle, order) in market_data.orders.iter() {
-            if order.resource == resource {
-                if highest_order.is_none() {
-                    highest_order = Some((*order_handle, order.max_price_per_unit));
-                } else if highest_order.unwrap().1 < order.max_price_per_unit {
-                    highest_order = Some((*order_handle, order.max_price_per_unit));
-                }
-            }
-        }
-        highest_order
+        market_data.order_index.get(&resource).copied().flatten()
     }
 
     pub fn place_offer(
@@ -80,9 +88,9 @@ impl Marketplace {
         if offer.amount <= 0.0 || offer.resource >= market_data.resource_count {
             return None;
         }
+        market_data.ensure_books();
         self.next_offer_id += 1;
-        market_data.offers.insert(self.next_offer_id, offer);
-        self.update_price_index(market_data);
+        market_data.insert_offer(self.next_offer_id, offer);
         Some(self.next_offer_id)
     }
 
@@ -95,12 +103,156 @@ impl Marketplace {
         if order.amount <= 0.0 || order.resource >= market_data.resource_count {
             return None;
         }
+        market_data.ensure_books();
         self.next_order_id += 1;
-        market_data.orders.insert(self.next_order_id, order);
-        self.update_order_index(market_data);
+        market_data.insert_order(self.next_order_id, order);
         Some(self.next_order_id)
     }
 
+    pub fn charge_fee(&mut self, company: &mut Company, fee: f64) {
+        if fee <= 0.0 {
+            return;
+        }
+        company.currency -= fee;
+        company.statistics.market_fees += fee;
+        self.operator_account += fee;
+    }
+
+    // Only the company which placed the order can cancel it
+    pub fn cancel_order(
+        &mut self,
+        company: CompanyHandle,
+        order_handle: OrderHandle,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+    ) -> bool {
+        if !market_data.is_order_owner(order_handle, company) {
+            return false;
+        }
+        let order = match market_data.remove_order(order_handle) {
+            Some(order) => order,
+            None => return false,
+        };
+        market_data.log_order_event(ListingEventKind::Cancelled, order_handle, &order);
+        // Pay back escrowed currency to the ordering company
+        if let Some(company) = order.company {
+            companies[company].add_currency(order.max_price_per_unit * order.amount);
+            let cancellation_fee = self.fees.cancellation_fee;
+            self.charge_fee(&mut companies[company], cancellation_fee);
+        }
+        true
+    }
+
+    pub fn cancel_offer(
+        &mut self,
+        company: CompanyHandle,
+        offer_handle: OfferHandle,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+    ) -> bool {
+        if !market_data.is_offer_owner(offer_handle, company) {
+            return false;
+        }
+        let offer = match market_data.remove_offer(offer_handle) {
+            Some(offer) => offer,
+            None => return false,
+        };
+        market_data.log_offer_event(ListingEventKind::Cancelled, offer_handle, &offer);
+        // Give back escrowed resources to the offering company
+        if let Some(company) = offer.company {
+            companies[company]
+                .stock
+                .add_to_stock(offer.resource, offer.amount);
+            let cancellation_fee = self.fees.cancellation_fee;
+            self.charge_fee(&mut companies[company], cancellation_fee);
+        }
+        true
+    }
+
+    pub fn amend_order(
+        &mut self,
+        company: CompanyHandle,
+        order_handle: OrderHandle,
+        amount: f64,
+        max_price_per_unit: f64,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+    ) -> bool {
+        if !market_data.is_order_owner(order_handle, company) {
+            return false;
+        }
+        if amount <= 0.0 {
+            return self.cancel_order(company, order_handle, market_data, companies);
+        }
+        let order = match market_data.orders.get_mut(&order_handle) {
+            Some(order) => order,
+            None => return false,
+        };
+        // Settle the difference between the old and the new escrow
+        let escrow_delta = amount * max_price_per_unit - order.amount * order.max_price_per_unit;
+        if companies[company].currency < escrow_delta {
+            return false;
+        }
+        companies[company].currency -= escrow_delta;
+        order.amount = amount;
+        market_data.reprice_order(order_handle, max_price_per_unit);
+        true
+    }
+
+    pub fn amend_offer(
+        &mut self,
+        company: CompanyHandle,
+        offer_handle: OfferHandle,
+        amount: f64,
+        price_per_unit: f64,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+    ) -> bool {
+        if !market_data.is_offer_owner(offer_handle, company) {
+            return false;
+        }
+        if amount <= 0.0 {
+            return self.cancel_offer(company, offer_handle, market_data, companies);
+        }
+        let offer = match market_data.offers.get_mut(&offer_handle) {
+            Some(offer) => offer,
+            None => return false,
+        };
+        // Settle the difference between the old and the new escrowed amount
+        let amount_delta = amount - offer.amount;
+        if amount_delta > 0.0 {
+            if !companies[company]
+                .stock
+                .remove_from_stock_if_possible(offer.resource, amount_delta)
+            {
+                return false;
+            }
+        } else {
+            companies[company]
+                .stock
+                .add_to_stock(offer.resource, -amount_delta);
+        }
+        offer.amount = amount;
+        market_data.reprice_offer(offer_handle, price_per_unit);
+        true
+    }
+
+    pub fn place_processor_offer(
+        &mut self,
+        offer: ProcessorOffer,
+        market_data: &mut MarketData,
+    ) -> ProcessorOfferHandle {
+        self.next_processor_offer_id += 1;
+        market_data
+            .processor_offers
+            .insert(self.next_processor_offer_id, offer);
+        self.next_processor_offer_id
+    }
+
+    pub fn place_processor_order(&self, order: ProcessorOrder, market_data: &mut MarketData) {
+        market_data.processor_orders.push(order);
+    }
+
     pub fn get_offer_by_handle(
         self,
         offer_handle: OfferHandle,
@@ -117,89 +269,341 @@ impl Marketplace {
         Some(&market_data.orders[&order_handle])
     }
 
-    fn execute_orders(&self, market_data: &mut MarketData, companies: &mut Vec<Company>) {
-        // Check all orders
-        for order in market_data.orders.values_mut() {
-            // We are trying to fulfill the hole order
-            while order.amount > 0.0 {
-                match self.get_cheapest_offer(order.resource, &market_data.offers) {
-                    Some(value) => {
-                        let offer_handle = value.0;
-                        let offer_price = value.1;
-                        if offer_price <= order.max_price_per_unit {
-                            match market_data.offers.get_mut(&offer_handle) {
-                                Some(offer) => {
-                                    if offer.amount < order.amount {
-                                        // Consume offer
-                                        order.amount -= offer.amount;
-                                        // Check if the order was created by a real company
-                                        match order.company {
-                                            // Give resources to company
-                                            Some(company) => {
-                                                companies[company]
-                                                    .stock
-                                                    .add_to_stock(order.resource, offer.amount);
-                                                // Give delta currency from max price back
-                                                let price_delta = (order.max_price_per_unit
-                                                    - offer.price_per_unit)
-                                                    * offer.amount;
-                                                companies[company].add_currency(price_delta);
-                                                // Pay out offering company
-                                                companies[company].add_currency(
-                                                    offer.amount * offer.price_per_unit,
-                                                );
-                                            }
-                                            None => {
-                                                // The order was created by a producer
-                                                // No one to add resources to and remove currency from
-                                            }
-                                        }
-
-                                        // We consumed the hole amount of the offer and must therefore remove it from the market
-                                        market_data.offers.remove(&offer_handle);
-                                    } else {
-                                        // Check if the order was created by a real company
-                                        match order.company {
-                                            Some(company) => {
-                                                // Give resources to ordering company
-                                                companies[company]
-                                                    .stock
-                                                    .add_to_stock(order.resource, order.amount);
-                                                // Give delta currency from max price back
-                                                let price_delta = (order.max_price_per_unit
-                                                    - offer.price_per_unit)
-                                                    * order.amount;
-                                                companies[company].add_currency(price_delta);
-                                                // Pay out offering company
-                                                companies[company]
-                                                    .add_currency(offer.amount * order.amount);
-                                            }
-                                            None => {
-                                                // The order was created by a producer
-                                                // No one to add resources to and remove currency from
-                                            }
-                                        }
-                                        // Reduce offer and order amount
-                                        offer.amount -= order.amount;
-                                        order.amount = 0.0;
-                                    }
-                                }
-                                None => {
-                                    break;
-                                }
-                            }
-                        } else {
-                            break;
-                        }
-                    }
-                    None => {
-                        break;
+    fn settle_trade(
+        &mut self,
+        trade: &Trade,
+        max_price_per_unit: f64,
+        companies: &mut [Company],
+        households: &mut [Household],
+        government: &mut Government,
+    ) {
+        // Check if the order was created by a real company
+        if let Some(buyer) = trade.buyer {
+            // Give resources to ordering company
+            companies[buyer]
+                .stock
+                .add_to_stock(trade.resource, trade.amount);
+            // Give delta currency from max price back
+            companies[buyer]
+                .add_currency((max_price_per_unit - trade.price_per_unit) * trade.amount);
+            if trade.seller_household.is_some() {
+                companies[buyer].statistics.wages_paid += trade.get_volume();
+                companies[buyer].statistics.hours_worked += trade.amount;
+            }
+            let commission = self.fees.get_commission(trade.get_volume());
+            self.charge_fee(&mut companies[buyer], commission);
+        }
+        // Pay out offering company or household
+        if let Some(seller) = trade.seller {
+            companies[seller].add_currency(trade.get_volume());
+            government.collect_vat(&mut companies[seller], trade.get_volume());
+            let commission = self.fees.get_commission(trade.get_volume());
+            self.charge_fee(&mut companies[seller], commission);
+        }
+        if let Some(household) = trade.seller_household {
+            households[household].receive_wage(trade.amount, trade.get_volume());
+        }
+    }
+
+    // Market offers are sold at the order's max price
+    fn get_fill_price(order: &Order, offer: &Offer) -> f64 {
+        match offer.order_type {
+            OrderType::Market => order.max_price_per_unit,
+            _ => offer.price_per_unit,
+        }
+    }
+
+    // Amount an order can buy at a price, company market orders buy as much as their escrowed
+    // budget allows
+    fn get_fillable_amount(order: &Order, amount: f64, budget: f64, price_per_unit: f64) -> f64 {
+        if order.order_type == OrderType::Market && order.company.is_some() {
+            amount.min(budget / price_per_unit)
+        } else {
+            amount
+        }
+    }
+
+    // Best offer the order can be matched with, fill-or-kill offers are only matched if the
+    // order can take their whole amount
+    fn get_matching_offer(
+        &self,
+        order: &Order,
+        market_data: &MarketData,
+    ) -> Option<(OfferHandle, f64)> {
+        let budget = order.amount * order.max_price_per_unit;
+        let best_offer = market_data
+            .offer_book
+            .iter(order.resource)
+            .find(|(offer_handle, _)| {
+                let offer = &market_data.offers[offer_handle];
+                let fillable_amount = Marketplace::get_fillable_amount(
+                    order,
+                    order.amount,
+                    budget,
+                    Marketplace::get_fill_price(order, offer),
+                );
+                !(offer.order_type == OrderType::FillOrKill && offer.amount > fillable_amount)
+            });
+        match best_offer {
+            Some((_, matching_price))
+                if order.order_type != OrderType::Market
+                    && matching_price > order.max_price_per_unit =>
+            {
+                None
+            }
+            _ => best_offer,
+        }
+    }
+
+    // Replays the matching of get_matching_offer without executing it, fill-or-kill offers are
+    // skipped once they exceed the amount left to fill
+    fn get_matchable_amount(&self, order: &Order, market_data: &MarketData) -> f64 {
+        let mut remaining_amount = order.amount;
+        let mut remaining_budget = order.amount * order.max_price_per_unit;
+        for (offer_handle, matching_price) in market_data.offer_book.iter(order.resource) {
+            if remaining_amount <= 0.0
+                || (order.order_type != OrderType::Market
+                    && matching_price > order.max_price_per_unit)
+            {
+                break;
+            }
+            let offer = &market_data.offers[&offer_handle];
+            let price_per_unit = Marketplace::get_fill_price(order, offer);
+            let fillable_amount = Marketplace::get_fillable_amount(
+                order,
+                remaining_amount,
+                remaining_budget,
+                price_per_unit,
+            );
+            if offer.order_type == OrderType::FillOrKill && offer.amount > fillable_amount {
+                continue;
+            }
+            let amount = offer.amount.min(fillable_amount);
+            remaining_amount -= amount;
+            remaining_budget -= amount * price_per_unit;
+        }
+        order.amount - remaining_amount
+    }
+
+    fn execute_orders(
+        &mut self,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+        households: &mut [Household],
+        government: &mut Government,
+    ) {
+        // Check all orders, the highest bids first
+        for resource in 0..market_data.resource_count {
+            let order_handles: Vec<OrderHandle> = market_data
+                .order_book
+                .iter(resource)
+                .map(|(order_handle, _)| order_handle)
+                .collect();
+            for order_handle in order_handles {
+                self.execute_order(order_handle, market_data, companies, households, government);
+            }
+        }
+    }
+
+    fn execute_order(
+        &mut self,
+        order_handle: OrderHandle,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+        households: &mut [Household],
+        government: &mut Government,
+    ) {
+        let order = &market_data.orders[&order_handle];
+        // Fill-or-kill orders are only executed if they can be filled completely
+        if order.order_type == OrderType::FillOrKill
+            && self.get_matchable_amount(order, market_data) < order.amount
+        {
+            return;
+        }
+        // We are trying to fulfill the hole order
+        while market_data.orders[&order_handle].amount > 0.0 {
+            let offer_handle =
+                match self.get_matching_offer(&market_data.orders[&order_handle], market_data) {
+                    Some((offer_handle, _)) => offer_handle,
+                    None => break,
+                };
+            let order = market_data.orders.get_mut(&order_handle).unwrap();
+            let offer = market_data.offers.get_mut(&offer_handle).unwrap();
+            let price_per_unit = Marketplace::get_fill_price(order, offer);
+            let budget = order.amount * order.max_price_per_unit;
+            let amount = offer.amount.min(Marketplace::get_fillable_amount(
+                order,
+                order.amount,
+                budget,
+                price_per_unit,
+            ));
+            if amount <= 0.0 {
+                break;
+            }
+            let trade = Trade {
+                resource: order.resource,
+                amount,
+                price_per_unit,
+                buyer: order.company,
+                seller: offer.company,
+                seller_household: offer.household,
+            };
+            self.fill(order, offer, &trade, companies, households, government);
+            let offer_is_consumed = offer.amount <= 0.0;
+            market_data.trades.push(trade);
+            if offer_is_consumed {
+                // We consumed the hole amount of the offer and must therefore remove it from the market
+                market_data.remove_offer(offer_handle);
+            }
+        }
+    }
+
+    // Clears every resource at a single uniform price instead of matching orders one by one
+    fn execute_call_auction(
+        &mut self,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+        households: &mut [Household],
+        government: &mut Government,
+    ) {
+        for resource in 0..market_data.resource_count {
+            let bids: Vec<AuctionBid> = market_data
+                .order_book
+                .iter(resource)
+                .map(|(order_handle, _)| (order_handle, &market_data.orders[&order_handle]))
+                .filter(|(_, order)| order.amount > 0.0)
+                .map(|(order_handle, order)| {
+                    let is_market = order.order_type == OrderType::Market;
+                    AuctionBid {
+                        order: order_handle,
+                        limit: match is_market {
+                            true => f64::INFINITY,
+                            false => order.max_price_per_unit,
+                        },
+                        amount: order.amount,
+                        budget: match is_market && order.company.is_some() {
+                            true => Some(order.amount * order.max_price_per_unit),
+                            false => None,
+                        },
+                        all_or_nothing: order.order_type == OrderType::FillOrKill,
                     }
+                })
+                .collect();
+            let asks: Vec<AuctionAsk> = market_data
+                .offer_book
+                .iter(resource)
+                .map(|(offer_handle, _)| (offer_handle, &market_data.offers[&offer_handle]))
+                .filter(|(_, offer)| offer.amount > 0.0)
+                .map(|(offer_handle, offer)| AuctionAsk {
+                    offer: offer_handle,
+                    limit: match offer.order_type {
+                        OrderType::Market => f64::NEG_INFINITY,
+                        _ => offer.price_per_unit,
+                    },
+                    amount: offer.amount,
+                    all_or_nothing: offer.order_type == OrderType::FillOrKill,
+                })
+                .collect();
+            let reference_price =
+                market_data.get_reference_price(resource, PriceReference::LastPrice);
+            let result = match clear_call_auction(bids, asks, reference_price) {
+                Some(result) => result,
+                None => continue,
+            };
+            for auction_fill in result.fills {
+                let order = market_data.orders.get_mut(&auction_fill.order).unwrap();
+                let offer = market_data.offers.get_mut(&auction_fill.offer).unwrap();
+                let trade = Trade {
+                    resource,
+                    amount: auction_fill.amount.min(order.amount).min(offer.amount),
+                    price_per_unit: result.price,
+                    buyer: order.company,
+                    seller: offer.company,
+                    seller_household: offer.household,
+                };
+                self.fill(order, offer, &trade, companies, households, government);
+                let offer_is_consumed = offer.amount <= 0.0;
+                market_data.trades.push(trade);
+                if offer_is_consumed {
+                    market_data.remove_offer(auction_fill.offer);
                 }
             }
         }
     }
 
+    // Reduces order and offer by the traded amount and settles the trade
+    fn fill(
+        &mut self,
+        order: &mut Order,
+        offer: &mut Offer,
+        trade: &Trade,
+        companies: &mut [Company],
+        households: &mut [Household],
+        government: &mut Government,
+    ) {
+        let budget = order.amount * order.max_price_per_unit;
+        offer.amount -= trade.amount;
+        order.amount -= trade.amount;
+        if order.order_type == OrderType::Market {
+            // The remaining budget stays escrowed for the remaining amount
+            self.settle_trade(
+                trade,
+                trade.price_per_unit,
+                companies,
+                households,
+                government,
+            );
+            let remaining_budget = budget - trade.get_volume();
+            if order.amount > 0.0 {
+                order.max_price_per_unit = remaining_budget / order.amount;
+            } else if let Some(company) = order.company {
+                companies[company].add_currency(remaining_budget);
+            }
+        } else {
+            self.settle_trade(
+                trade,
+                order.max_price_per_unit,
+                companies,
+                households,
+                government,
+            );
+        }
+    }
+
+    // Cancel unfilled remainders of market, immediate-or-cancel and fill-or-kill listings
+    fn cleanup_immediate_listings(
+        &mut self,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+    ) {
+        let mut immediate_orders: Vec<OrderHandle> = vec![];
+        for (order_handle, order) in market_data.orders.iter() {
+            if order.order_type.is_immediate() {
+                immediate_orders.push(*order_handle);
+            }
+        }
+        for order_handle in immediate_orders {
+            let order = market_data.remove_order(order_handle).unwrap();
+            market_data.log_order_event(ListingEventKind::Cancelled, order_handle, &order);
+            if let Some(company) = order.company {
+                companies[company].add_currency(order.max_price_per_unit * order.amount);
+            }
+        }
+        let mut immediate_offers: Vec<OfferHandle> = vec![];
+        for (offer_handle, offer) in market_data.offers.iter() {
+            if offer.order_type.is_immediate() {
+                immediate_offers.push(*offer_handle);
+            }
+        }
+        for offer_handle in immediate_offers {
+            let offer = market_data.remove_offer(offer_handle).unwrap();
+            market_data.log_offer_event(ListingEventKind::Cancelled, offer_handle, &offer);
+            if offer.company.is_some() {
+                self.returned_offers.push(offer);
+            }
+        }
+    }
+
     fn cleanup_complete_orders(&self, market_data: &mut MarketData) {
         let mut complete_orders: Vec<OrderHandle> = vec![];
         for (order_handle, order) in market_data.orders.iter() {
@@ -208,60 +612,466 @@ impl Marketplace {
             }
         }
         for order_handle in complete_orders {
-            market_data.orders.remove(&order_handle);
+            market_data.remove_order(order_handle);
         }
     }
 
-    fn cleanup_dead_orders(&self, market_data: &mut MarketData, companies: &mut Vec<Company>) {
+    fn cleanup_dead_orders(&mut self, market_data: &mut MarketData, companies: &mut [Company]) {
         let mut complete_orders: Vec<OrderHandle> = vec![];
         for (order_handle, order) in market_data.orders.iter_mut() {
+            if !order.order_type.expires() {
+                continue;
+            }
             order.time_to_live -= 1;
             if order.time_to_live > 0 {
                 continue;
             }
             // Pay back ordering company
-            match order.company {
-                Some(company) => {
-                    companies[company].add_currency(order.max_price_per_unit * order.amount);
-                }
-                None => {}
+            if let Some(company) = order.company {
+                companies[company].add_currency(order.max_price_per_unit * order.amount);
+                let expiry_fee = self.fees.expiry_fee;
+                self.charge_fee(&mut companies[company], expiry_fee);
             }
             complete_orders.push(*order_handle);
         }
         for order_handle in complete_orders {
-            market_data.orders.remove(&order_handle);
+            if let Some(order) = market_data.remove_order(order_handle) {
+                market_data.log_order_event(ListingEventKind::Expired, order_handle, &order);
+            }
         }
     }
 
-    fn cleanup_dead_offers(&self, market_data: &mut MarketData, companies: &mut Vec<Company>) {
-        let mut complete_offers: Vec<OrderHandle> = vec![];
+    fn cleanup_dead_offers(&mut self, market_data: &mut MarketData, companies: &mut [Company]) {
+        let mut complete_offers: Vec<OfferHandle> = vec![];
         for (offer_handle, offer) in market_data.offers.iter_mut() {
+            if !offer.order_type.expires() {
+                continue;
+            }
             offer.time_to_live -= 1;
             if offer.time_to_live > 0 {
                 continue;
             }
-            // Give back resources to offering company
-            match offer.company {
-                Some(company) => {
-                    companies[company]
-                        .stock
-                        .add_to_stock(offer.resource, offer.amount);
-                }
-                None => {}
+            if let Some(company) = offer.company {
+                let expiry_fee = self.fees.expiry_fee;
+                self.charge_fee(&mut companies[company], expiry_fee);
             }
             complete_offers.push(*offer_handle);
         }
 
-        for order_handle in complete_offers {
-            market_data.offers.remove(&order_handle);
+        for offer_handle in complete_offers {
+            if let Some(offer) = market_data.remove_offer(offer_handle) {
+                market_data.log_offer_event(ListingEventKind::Expired, offer_handle, &offer);
+                // Give back resources to offering company
+                if offer.company.is_some() {
+                    self.returned_offers.push(offer);
+                }
+            }
+        }
+    }
+
+    fn execute_processor_orders(&self, market_data: &mut MarketData, companies: &mut [Company]) {
+        // Processor orders are executed in the order they were placed, all unfilled orders are
+        // refunded at the end of the tick
+        for order in market_data.processor_orders.drain(..) {
+            let offer_is_affordable = match market_data.processor_offers.get(&order.offer) {
+                Some(offer) => offer.price <= order.max_price && offer.company != order.company,
+                None => false,
+            };
+            if !offer_is_affordable {
+                companies[order.company].add_currency(order.max_price);
+                continue;
+            }
+            let offer = market_data.processor_offers.remove(&order.offer).unwrap();
+            // Give delta currency from max price back
+            companies[order.company].add_currency(order.max_price - offer.price);
+            companies[order.company].processors.push(offer.processor);
+            // Pay out offering company
+            companies[offer.company].add_currency(offer.price);
+        }
+    }
+
+    fn cleanup_dead_processor_offers(
+        &self,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+    ) {
+        let mut dead_offers: Vec<ProcessorOfferHandle> = vec![];
+        for (offer_handle, offer) in market_data.processor_offers.iter_mut() {
+            offer.time_to_live -= 1;
+            if offer.time_to_live > 0 {
+                continue;
+            }
+            dead_offers.push(*offer_handle);
+        }
+        for offer_handle in dead_offers {
+            // Give processor back to offering company
+            let offer = market_data.processor_offers.remove(&offer_handle).unwrap();
+            companies[offer.company].processors.push(offer.processor);
+        }
+    }
+
+    pub fn apply_spoilage<R: Rng>(
+        &self,
+        market_data: &mut MarketData,
+        resource_data: &ResourceData,
+        companies: &mut [Company],
+        rng: &mut R,
+    ) {
+        let mut spoiled_offers: Vec<OfferHandle> = vec![];
+        // Draw in handle order, so a seeded generator gives the same decay every run
+        let mut offers: Vec<(&OfferHandle, &mut Offer)> = market_data.offers.iter_mut().collect();
+        offers.sort_by_key(|(offer_handle, _)| **offer_handle);
+        for (offer_handle, offer) in offers {
+            let resource = match resource_data.resources.get(offer.resource) {
+                Some(resource) => resource,
+                None => continue,
+            };
+            let decay = resource.get_decay(offer.amount, rng);
+            if decay <= 0.0 {
+                continue;
+            }
+            offer.amount -= decay;
+            if let Some(company) = offer.company {
+                companies[company]
+                    .statistics
+                    .add_spoilage(offer.resource, decay);
+            }
+            if offer.amount <= 0.0 {
+                spoiled_offers.push(*offer_handle);
+            }
+        }
+        for offer_handle in spoiled_offers {
+            market_data.remove_offer(offer_handle);
         }
     }
 
-    pub fn tick(&self, market_data: &mut MarketData, companies: &mut Vec<Company>) {
-        self.execute_orders(market_data, companies);
+    pub fn tick(
+        &mut self,
+        market_data: &mut MarketData,
+        companies: &mut [Company],
+        households: &mut [Household],
+        government: &mut Government,
+    ) {
+        market_data.ensure_books();
+        match self.market_mode {
+            MarketMode::Continuous => {
+                self.execute_orders(market_data, companies, households, government)
+            }
+            MarketMode::CallAuction => {
+                self.execute_call_auction(market_data, companies, households, government)
+            }
+        }
+        self.execute_processor_orders(market_data, companies);
         self.cleanup_complete_orders(market_data);
+        self.cleanup_immediate_listings(market_data, companies);
         self.cleanup_dead_orders(market_data, companies);
         self.cleanup_dead_offers(market_data, companies);
-        self.update_order_index(market_data);
+        self.cleanup_dead_processor_offers(market_data, companies);
+        market_data.record_candles();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reinforcement_learning::controller::ControllerType;
+    use crate::reinforcement_learning::heuristic::HeuristicStrategy;
+
+    // Two companies with 100 currency and 5 units of the only resource each
+    fn setup() -> (Marketplace, MarketData, Vec<Company>) {
+        let mut market_data = MarketData::new(1);
+        market_data.resource_count = 1;
+        let companies = (0..2)
+            .map(|company_handle| {
+                let mut company = Company::new(
+                    "Company",
+                    company_handle,
+                    1,
+                    0,
+                    0,
+                    0.9,
+                    ControllerType::Heuristic(HeuristicStrategy::BreakEvenTrader),
+                );
+                company.currency = 100.0;
+                company.stock.add_to_stock(0, 5.0);
+                company
+            })
+            .collect();
+        (Marketplace::new(), market_data, companies)
+    }
+
+    // Escrows the order's currency like the world does when a company places an order
+    fn place_company_order(
+        market_place: &mut Marketplace,
+        market_data: &mut MarketData,
+        company: &mut Company,
+        amount: f64,
+        max_price_per_unit: f64,
+        order_type: OrderType,
+    ) -> OrderHandle {
+        company.currency -= amount * max_price_per_unit;
+        let order = Order {
+            company: Some(company.id),
+            resource: 0,
+            amount,
+            max_price_per_unit,
+            time_to_live: 10,
+            order_type,
+        };
+        market_place.place_order(order, market_data).unwrap()
+    }
+
+    // Escrows the offer's resources like the world does when a company places an offer
+    fn place_company_offer(
+        market_place: &mut Marketplace,
+        market_data: &mut MarketData,
+        company: &mut Company,
+        amount: f64,
+        price_per_unit: f64,
+        order_type: OrderType,
+    ) -> OfferHandle {
+        assert!(company.stock.remove_from_stock_if_possible(0, amount));
+        let offer = Offer {
+            resource: 0,
+            amount,
+            price_per_unit,
+            company: Some(company.id),
+            time_to_live: 10,
+            household: None,
+            order_type,
+        };
+        market_place.place_offer(offer, market_data).unwrap()
+    }
+
+    #[test]
+    fn cancel_order_refunds_escrow() {
+        let (mut market_place, mut market_data, mut companies) = setup();
+        let order_handle = place_company_order(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[0],
+            2.0,
+            10.0,
+            OrderType::Limit,
+        );
+        assert_eq!(companies[0].currency, 80.0);
+        assert!(market_place.cancel_order(0, order_handle, &mut market_data, &mut companies));
+        assert_eq!(companies[0].currency, 100.0);
+        assert!(market_data.orders.is_empty());
+        assert_eq!(market_data.order_index[&0], None);
+    }
+
+    #[test]
+    fn cancel_order_requires_owner() {
+        let (mut market_place, mut market_data, mut companies) = setup();
+        let order_handle = place_company_order(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[0],
+            2.0,
+            10.0,
+            OrderType::Limit,
+        );
+        assert!(!market_place.cancel_order(1, order_handle, &mut market_data, &mut companies));
+        assert_eq!(companies[0].currency, 80.0);
+        assert_eq!(companies[1].currency, 100.0);
+        assert_eq!(market_data.orders[&order_handle].amount, 2.0);
+        assert_eq!(market_data.order_index[&0], Some((order_handle, 10.0)));
+    }
+
+    #[test]
+    fn cancel_offer_returns_stock() {
+        let (mut market_place, mut market_data, mut companies) = setup();
+        let offer_handle = place_company_offer(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[0],
+            3.0,
+            10.0,
+            OrderType::Limit,
+        );
+        assert_eq!(companies[0].stock.get_amount(0), 2.0);
+        assert!(market_place.cancel_offer(0, offer_handle, &mut market_data, &mut companies));
+        assert_eq!(companies[0].stock.get_amount(0), 5.0);
+        assert!(market_data.offers.is_empty());
+        assert_eq!(market_data.price_index[&0], None);
+    }
+
+    #[test]
+    fn cancel_offer_requires_owner() {
+        let (mut market_place, mut market_data, mut companies) = setup();
+        let offer_handle = place_company_offer(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[0],
+            3.0,
+            10.0,
+            OrderType::Limit,
+        );
+        assert!(!market_place.cancel_offer(1, offer_handle, &mut market_data, &mut companies));
+        assert_eq!(companies[0].stock.get_amount(0), 2.0);
+        assert_eq!(companies[1].stock.get_amount(0), 5.0);
+        assert_eq!(market_data.offers[&offer_handle].amount, 3.0);
+        assert_eq!(market_data.price_index[&0], Some((offer_handle, 10.0)));
+    }
+
+    #[test]
+    fn amend_order_settles_escrow_difference() {
+        let (mut market_place, mut market_data, mut companies) = setup();
+        let order_handle = place_company_order(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[0],
+            2.0,
+            10.0,
+            OrderType::Limit,
+        );
+        assert!(market_place.amend_order(
+            0,
+            order_handle,
+            3.0,
+            20.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[0].currency, 40.0);
+        assert_eq!(market_data.orders[&order_handle].amount, 3.0);
+        assert_eq!(market_data.order_index[&0], Some((order_handle, 20.0)));
+        // Lowering the amount refunds the difference
+        assert!(market_place.amend_order(
+            0,
+            order_handle,
+            1.0,
+            20.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[0].currency, 80.0);
+        // Amendments which can't be escrowed leave the order unchanged
+        assert!(!market_place.amend_order(
+            0,
+            order_handle,
+            10.0,
+            20.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[0].currency, 80.0);
+        assert_eq!(market_data.orders[&order_handle].amount, 1.0);
+        assert_eq!(market_data.order_index[&0], Some((order_handle, 20.0)));
+        // Other companies can't amend the order
+        assert!(!market_place.amend_order(
+            1,
+            order_handle,
+            2.0,
+            10.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[1].currency, 100.0);
+        assert!(market_place.cancel_order(0, order_handle, &mut market_data, &mut companies));
+        assert_eq!(companies[0].currency, 100.0);
+        assert!(market_data.orders.is_empty());
+    }
+
+    #[test]
+    fn amend_offer_settles_escrow_difference() {
+        let (mut market_place, mut market_data, mut companies) = setup();
+        let offer_handle = place_company_offer(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[0],
+            2.0,
+            10.0,
+            OrderType::Limit,
+        );
+        assert!(market_place.amend_offer(
+            0,
+            offer_handle,
+            4.0,
+            8.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[0].stock.get_amount(0), 1.0);
+        assert_eq!(market_data.offers[&offer_handle].amount, 4.0);
+        assert_eq!(market_data.price_index[&0], Some((offer_handle, 8.0)));
+        // Lowering the amount returns the difference
+        assert!(market_place.amend_offer(
+            0,
+            offer_handle,
+            1.0,
+            8.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[0].stock.get_amount(0), 4.0);
+        // Amendments which can't be escrowed leave the offer unchanged
+        assert!(!market_place.amend_offer(
+            0,
+            offer_handle,
+            10.0,
+            12.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[0].stock.get_amount(0), 4.0);
+        assert_eq!(market_data.offers[&offer_handle].amount, 1.0);
+        assert_eq!(market_data.price_index[&0], Some((offer_handle, 8.0)));
+        // Other companies can't amend the offer
+        assert!(!market_place.amend_offer(
+            1,
+            offer_handle,
+            2.0,
+            10.0,
+            &mut market_data,
+            &mut companies
+        ));
+        assert_eq!(companies[1].stock.get_amount(0), 5.0);
+        assert!(market_place.cancel_offer(0, offer_handle, &mut market_data, &mut companies));
+        assert_eq!(companies[0].stock.get_amount(0), 5.0);
+        assert!(market_data.offers.is_empty());
+    }
+
+    // A market order's budget of 40 buys only 3.33 units at 12, so the fill-or-kill offer of 4
+    // units must not trade at all
+    #[test]
+    fn market_order_budget_skips_fill_or_kill_offer() {
+        let (mut market_place, mut market_data, mut companies) = setup();
+        let order_handle = place_company_order(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[0],
+            4.0,
+            10.0,
+            OrderType::Market,
+        );
+        let offer_handle = place_company_offer(
+            &mut market_place,
+            &mut market_data,
+            &mut companies[1],
+            4.0,
+            12.0,
+            OrderType::FillOrKill,
+        );
+        market_place.tick(
+            &mut market_data,
+            &mut companies,
+            &mut [],
+            &mut Government::new(),
+        );
+        assert!(market_data.trades.is_empty() && market_data.log.trades.is_empty());
+        assert_eq!(companies[0].currency, 100.0);
+        assert_eq!(market_place.returned_offers.len(), 1);
+        assert_eq!(market_place.returned_offers[0].amount, 4.0);
+        // Unfilled immediate listings are logged as cancelled
+        let cancelled: Vec<usize> = market_data
+            .log
+            .listing_events
+            .iter()
+            .filter(|event| event.kind == ListingEventKind::Cancelled)
+            .map(|event| event.handle)
+            .collect();
+        assert_eq!(cancelled, vec![order_handle, offer_handle]);
     }
 }
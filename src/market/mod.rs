@@ -1,3 +1,15 @@
+pub mod book_index;
+pub mod book_request;
+pub mod call_auction;
+pub mod candle;
+pub mod market_fees;
+pub mod market_log;
+pub mod market_mode;
 pub mod marketplace;
 pub mod offer;
 pub mod order;
+pub mod order_type;
+pub mod price_reference;
+pub mod processor_offer;
+pub mod shipment;
+pub mod trade;
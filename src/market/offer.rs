@@ -1,5 +1,7 @@
 use crate::economy::company::CompanyHandle;
+use crate::economy::household::HouseholdHandle;
 use crate::economy::resource::ResourceHandle;
+use crate::market::order_type::OrderType;
 
 use serde::{Deserialize, Serialize};
 
@@ -11,6 +13,8 @@ pub struct UnprocessedOffer {
     pub amount: f64,
     pub price_per_unit: f64,
     pub time_to_live: usize,
+    #[serde(default)]
+    pub order_type: OrderType,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -20,4 +24,18 @@ pub struct Offer {
     pub price_per_unit: f64,
     pub company: Option<CompanyHandle>,
     pub time_to_live: usize,
+    #[serde(default)]
+    pub household: Option<HouseholdHandle>,
+    #[serde(default)]
+    pub order_type: OrderType,
+}
+
+impl Offer {
+    // Price used to rank offers while matching, market offers are always matched first
+    pub fn get_matching_price(&self) -> f64 {
+        match self.order_type {
+            OrderType::Market => 0.0,
+            _ => self.price_per_unit,
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use crate::economy::company::CompanyHandle;
 use crate::economy::resource::ResourceHandle;
+use crate::market::order_type::OrderType;
 use serde::{Deserialize, Serialize};
 
 pub type OrderHandle = usize;
@@ -9,6 +10,8 @@ pub struct UnprocessedOrder {
     pub amount: f64,
     pub max_price_per_unit: f64,
     pub time_to_live: usize,
+    #[serde(default)]
+    pub order_type: OrderType,
 }
 
 #[derive(Serialize, Deserialize, Default)]
@@ -18,4 +21,6 @@ pub struct Order {
     pub amount: f64,
     pub max_price_per_unit: f64,
     pub time_to_live: usize,
+    #[serde(default)]
+    pub order_type: OrderType,
 }
@@ -0,0 +1,31 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum OrderType {
+    // Rests on the market at its limit price until filled or expired
+    #[default]
+    Limit,
+    // Trades at any price; for orders the escrow is the budget, for offers the trade happens at
+    // the order's max price. Unfilled remainders are cancelled after matching.
+    Market,
+    // Trades at the limit price as far as possible, unfilled remainders are cancelled
+    ImmediateOrCancel,
+    // Trades the whole amount at the limit price or nothing at all
+    FillOrKill,
+    // Like limit, but never expires
+    GoodTillCancelled,
+}
+
+impl OrderType {
+    // Listings which do not rest on the market after matching
+    pub fn is_immediate(&self) -> bool {
+        matches!(
+            self,
+            OrderType::Market | OrderType::ImmediateOrCancel | OrderType::FillOrKill
+        )
+    }
+
+    pub fn expires(&self) -> bool {
+        *self != OrderType::GoodTillCancelled
+    }
+}
@@ -0,0 +1,12 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, Default, Debug)]
+pub enum PriceReference {
+    // Highest open order
+    BestBid,
+    // Cheapest open offer
+    #[default]
+    BestAsk,
+    // Price of the last trade
+    LastPrice,
+}
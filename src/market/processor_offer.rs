@@ -0,0 +1,40 @@
+use crate::economy::company::CompanyHandle;
+use crate::economy::processor::Processor;
+use crate::economy::recipe::RecipeHandle;
+use serde::{Deserialize, Serialize};
+
+pub type ProcessorOfferHandle = usize;
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UnprocessedProcessorOffer {
+    pub processor: Processor,
+    pub price: f64,
+    pub time_to_live: usize,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct ProcessorOffer {
+    pub processor: Processor,
+    pub price: f64,
+    pub company: CompanyHandle,
+    pub time_to_live: usize,
+}
+
+impl ProcessorOffer {
+    pub fn recipe(&self) -> RecipeHandle {
+        self.processor.recipe
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct UnprocessedProcessorOrder {
+    pub offer: ProcessorOfferHandle,
+    pub max_price: f64,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone)]
+pub struct ProcessorOrder {
+    pub offer: ProcessorOfferHandle,
+    pub max_price: f64,
+    pub company: CompanyHandle,
+}
@@ -0,0 +1,23 @@
+use crate::economy::company::CompanyHandle;
+use crate::market::offer::UnprocessedOffer;
+use crate::world_data::region_data::RegionHandle;
+use serde::{Deserialize, Serialize};
+
+// Export requested by a company, the offer is placed in the destination region on arrival
+#[derive(Serialize, Deserialize, Clone)]
+pub struct UnprocessedShipment {
+    pub destination: RegionHandle,
+    pub offer: UnprocessedOffer,
+}
+
+// Resources on their way to another region
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Shipment {
+    pub company: CompanyHandle,
+    pub destination: RegionHandle,
+    pub offer: UnprocessedOffer,
+    pub remaining_ticks: usize,
+    // Unsold resources on their way back, they go into the company's stock on arrival
+    #[serde(default)]
+    pub is_return: bool,
+}
@@ -0,0 +1,20 @@
+use crate::economy::company::CompanyHandle;
+use crate::economy::household::HouseholdHandle;
+use crate::economy::resource::ResourceHandle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Trade {
+    pub resource: ResourceHandle,
+    pub amount: f64,
+    pub price_per_unit: f64,
+    pub buyer: Option<CompanyHandle>,
+    pub seller: Option<CompanyHandle>,
+    pub seller_household: Option<HouseholdHandle>,
+}
+
+impl Trade {
+    pub fn get_volume(&self) -> f64 {
+        self.amount * self.price_per_unit
+    }
+}
@@ -1,38 +1,638 @@
+use crate::economy::company::Company;
+use crate::economy::contract::Position;
+use crate::economy::resource::ResourceHandle;
+use crate::market::order_type::OrderType;
+use crate::market::price_reference::PriceReference;
+use crate::world_data::market_data::MarketData;
+use crate::world_data::processor_data::ProcessorData;
+use crate::world_data::storage_data::StorageData;
 use serde::{Deserialize, Serialize};
 
 #[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
 pub enum CompanyAction {
     Nothing,
+    // Doing nothing with a resource, so every bundle group has a no-op of its own
+    NothingWithResource(usize),
     BuyProcessor(usize),
     SellProcessor(usize),
+    SellProcessorOfRecipe(usize),
+    OfferProcessor(usize, usize),
+    OfferProcessorOfRecipe(usize, usize),
+    BuyUsedProcessor(usize),
+    BuyWarehouse,
+    Hire(usize, usize),
+    Fire(usize),
     BuyResource(usize, usize, usize),
     SellResource(usize, usize, usize),
+    // Price as percentage of the reference price
+    BuyResourceRelative(usize, usize, usize, PriceReference),
+    SellResourceRelative(usize, usize, usize, PriceReference),
+    BuyResourceWithType(usize, usize, usize, OrderType),
+    SellResourceWithType(usize, usize, usize, OrderType),
+    CancelOrders(usize),
+    CancelOffers(usize),
+    AmendOrders(usize, usize),
+    AmendOffers(usize, usize),
+    // Changes the amounts of the open orders and offers, keeping their prices
+    AmendOrderAmounts(usize, usize),
+    AmendOfferAmounts(usize, usize),
+    ExportResource(usize, usize, usize, usize),
+    BuyFuture(usize, usize, usize, usize),
+    SellFuture(usize, usize, usize, usize),
+    ProposeSupplyContract(usize, usize, usize, usize, usize, Position),
+    AcceptSupplyContract(usize, Position),
+    // Amount and price are continuous parameters chosen together with the action
+    Parameterized(ParameterizedAction),
+}
+
+#[derive(PartialEq, Eq, Hash, Clone, Copy, Serialize, Deserialize, Debug)]
+pub enum ParameterizedAction {
+    BuyResource(ResourceHandle),
+    SellResource(ResourceHandle),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ActionMode {
+    // One action per tick
+    #[default]
+    Single,
+    // A fixed number of distinct actions per tick
+    Multiple(usize),
+    // One action per resource plus one capital action per tick
+    Bundle,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ActionSpaceDefinition {
+    // Amounts of the buy, sell, futures and supply contract actions
+    pub quantities: Vec<usize>,
+    // Absolute prices per unit of the same actions, market orders spend up to this price per unit
+    pub price_levels: Vec<usize>,
+    // Prices per unit as percentage of the reference prices
+    pub relative_prices: Vec<usize>,
+    // Order types besides limit, placed at the absolute price levels
+    pub order_types: Vec<OrderType>,
+    pub buy_reference: PriceReference,
+    pub sell_reference: PriceReference,
+    // Scrap processors by recipe instead of by their position in the company
+    pub sell_processors_by_recipe: bool,
+    pub processor_slots: usize,
+    // Prices of second-hand processor offers as percentage of the new price
+    pub processor_offer_prices: Vec<usize>,
+    // Workers per hiring and firing action and the wages offered when hiring
+    pub worker_quantities: Vec<usize>,
+    pub wage_levels: Vec<usize>,
+    // Futures periods until delivery
+    pub future_maturities: Vec<usize>,
+    // Ticks between the deliveries of supply contracts and their number of deliveries
+    pub supply_intervals: Vec<usize>,
+    pub supply_deliveries: Vec<usize>,
+    // Also buy and sell with continuous amounts and prices relative to the reference prices
+    pub hybrid: bool,
+    // Agents only choose among actions which are valid for the company
+    pub action_masking: bool,
+    pub action_mode: ActionMode,
+}
+
+impl Default for ActionSpaceDefinition {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ActionSpaceDefinition {
+    pub fn new() -> ActionSpaceDefinition {
+        ActionSpaceDefinition {
+            quantities: vec![5],
+            price_levels: (0..10).map(|k| 2_usize.pow(k)).collect(),
+            relative_prices: vec![],
+            order_types: vec![
+                OrderType::ImmediateOrCancel,
+                OrderType::FillOrKill,
+                OrderType::GoodTillCancelled,
+            ],
+            buy_reference: PriceReference::BestAsk,
+            sell_reference: PriceReference::BestBid,
+            sell_processors_by_recipe: false,
+            processor_slots: 10,
+            processor_offer_prices: vec![50, 75, 100],
+            worker_quantities: vec![1],
+            wage_levels: (0..4).map(|k| 2_usize.pow(k)).collect(),
+            future_maturities: vec![1],
+            supply_intervals: vec![10],
+            supply_deliveries: vec![10],
+            hybrid: false,
+            action_masking: true,
+            action_mode: ActionMode::Single,
+        }
+    }
+}
+
+// Ranges of the continuous parameters of the parameterized actions
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HybridActionSpace {
+    // Indices of the parameterized actions in the action space
+    pub parameterized_actions: Vec<usize>,
+    pub amount_range: (f64, f64),
+    // Price as percentage of the reference price
+    pub price_range: (f64, f64),
+    pub buy_reference: PriceReference,
+    pub sell_reference: PriceReference,
+}
+
+impl HybridActionSpace {
+    fn new(definition: &ActionSpaceDefinition, actions: &[CompanyAction]) -> HybridActionSpace {
+        let quantities = &definition.quantities;
+        let relative_prices = &definition.relative_prices;
+        HybridActionSpace {
+            parameterized_actions: actions
+                .iter()
+                .enumerate()
+                .filter(|(_, action)| matches!(action, CompanyAction::Parameterized(_)))
+                .map(|(index, _)| index)
+                .collect(),
+            amount_range: (
+                quantities.iter().copied().min().unwrap_or(1) as f64,
+                quantities.iter().copied().max().unwrap_or(1) as f64,
+            ),
+            price_range: (
+                relative_prices.iter().copied().min().unwrap_or(100) as f64,
+                relative_prices.iter().copied().max().unwrap_or(100) as f64,
+            ),
+            buy_reference: definition.buy_reference,
+            sell_reference: definition.sell_reference,
+        }
+    }
+
+    pub fn is_parameterized(&self, action: usize) -> bool {
+        self.parameterized_actions.contains(&action)
+    }
+
+    // Clamps the continuous parameters into their ranges
+    pub fn get_parameters(&self, amount: f64, price_percentage: f64) -> (f64, f64) {
+        (
+            amount.clamp(self.amount_range.0, self.amount_range.1),
+            price_percentage.clamp(self.price_range.0, self.price_range.1),
+        )
+    }
+
+    // Middle of the ranges, used when an agent without continuous outputs chooses the action
+    pub fn get_default_parameters(&self) -> (f64, f64) {
+        (
+            (self.amount_range.0 + self.amount_range.1) / 2.0,
+            (self.price_range.0 + self.price_range.1) / 2.0,
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum HybridAction {
+    Discrete(usize),
+    Parameterized {
+        action: usize,
+        amount: f64,
+        price_percentage: f64,
+    },
+}
+
+impl HybridAction {
+    // Index of the chosen action in the action space
+    pub fn get_action(&self) -> usize {
+        match *self {
+            HybridAction::Discrete(action) | HybridAction::Parameterized { action, .. } => action,
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct ActionSpace {
     pub actions: Vec<CompanyAction>,
+    #[serde(default)]
+    pub definition: ActionSpaceDefinition,
+    // Tradeable resources, labor is traded through hiring and firing
+    #[serde(default)]
+    pub resources: Vec<ResourceHandle>,
+    #[serde(default)]
+    pub labor_resource: Option<ResourceHandle>,
+    #[serde(default)]
+    pub hybrid: Option<HybridActionSpace>,
 }
 
 impl ActionSpace {
-    pub fn new(resource_count: usize, recipe_count: usize) -> ActionSpace {
+    pub fn new(
+        definition: &ActionSpaceDefinition,
+        resource_count: usize,
+        recipe_count: usize,
+        labor_resource: Option<ResourceHandle>,
+    ) -> ActionSpace {
+        let resources: Vec<ResourceHandle> = (0..resource_count)
+            .filter(|resource| Some(*resource) != labor_resource)
+            .collect();
         let mut actionspace: Vec<CompanyAction> = Vec::new();
         actionspace.push(CompanyAction::Nothing);
         for i in 0..recipe_count {
             actionspace.push(CompanyAction::BuyProcessor(i));
         }
-        for i in 0..10 {
-            actionspace.push(CompanyAction::SellProcessor(i));
+        for i in 0..recipe_count {
+            actionspace.push(CompanyAction::BuyUsedProcessor(i));
+        }
+        actionspace.push(CompanyAction::BuyWarehouse);
+        for workers in definition.worker_quantities.iter().copied() {
+            for wage in definition.wage_levels.iter().copied() {
+                actionspace.push(CompanyAction::Hire(workers, wage));
+            }
+            actionspace.push(CompanyAction::Fire(workers));
         }
-        for i in 1..resource_count {
-            for k in 0..10 {
-                let k_value = (2 as usize).pow(k);
-                actionspace.push(CompanyAction::BuyResource(i, 5, k_value));
-                actionspace.push(CompanyAction::SellResource(i, 5, k_value));
+        // Offer processors on the second-hand market at a percentage of their new price
+        if definition.sell_processors_by_recipe {
+            for i in 0..recipe_count {
+                actionspace.push(CompanyAction::SellProcessorOfRecipe(i));
+                for price_percentage in definition.processor_offer_prices.iter().copied() {
+                    actionspace.push(CompanyAction::OfferProcessorOfRecipe(i, price_percentage));
+                }
+            }
+        } else {
+            for i in 0..definition.processor_slots {
+                actionspace.push(CompanyAction::SellProcessor(i));
+                for price_percentage in definition.processor_offer_prices.iter().copied() {
+                    actionspace.push(CompanyAction::OfferProcessor(i, price_percentage));
+                }
             }
         }
+        for i in resources.iter().copied() {
+            if definition.action_mode == ActionMode::Bundle {
+                actionspace.push(CompanyAction::NothingWithResource(i));
+            }
+            if definition.hybrid {
+                actionspace.push(CompanyAction::Parameterized(
+                    ParameterizedAction::BuyResource(i),
+                ));
+                actionspace.push(CompanyAction::Parameterized(
+                    ParameterizedAction::SellResource(i),
+                ));
+            }
+            for amount in definition.quantities.iter().copied() {
+                for price in definition.price_levels.iter().copied() {
+                    actionspace.push(CompanyAction::BuyResource(i, amount, price));
+                    actionspace.push(CompanyAction::SellResource(i, amount, price));
+                    for order_type in definition.order_types.iter().copied() {
+                        actionspace.push(CompanyAction::BuyResourceWithType(
+                            i, amount, price, order_type,
+                        ));
+                        actionspace.push(CompanyAction::SellResourceWithType(
+                            i, amount, price, order_type,
+                        ));
+                    }
+                }
+                for price_percentage in definition.relative_prices.iter().copied() {
+                    actionspace.push(CompanyAction::BuyResourceRelative(
+                        i,
+                        amount,
+                        price_percentage,
+                        definition.buy_reference,
+                    ));
+                    actionspace.push(CompanyAction::SellResourceRelative(
+                        i,
+                        amount,
+                        price_percentage,
+                        definition.sell_reference,
+                    ));
+                }
+            }
+            for price in definition.price_levels.iter().copied() {
+                actionspace.push(CompanyAction::AmendOrders(i, price));
+                actionspace.push(CompanyAction::AmendOffers(i, price));
+            }
+            for amount in definition.quantities.iter().copied() {
+                actionspace.push(CompanyAction::AmendOrderAmounts(i, amount));
+                actionspace.push(CompanyAction::AmendOfferAmounts(i, amount));
+            }
+            // Market orders spend up to their budget, market offers sell to the best bids
+            for amount in definition.quantities.iter().copied() {
+                for price in definition.price_levels.iter().copied() {
+                    actionspace.push(CompanyAction::BuyResourceWithType(
+                        i,
+                        amount,
+                        price,
+                        OrderType::Market,
+                    ));
+                }
+                actionspace.push(CompanyAction::SellResourceWithType(
+                    i,
+                    amount,
+                    0,
+                    OrderType::Market,
+                ));
+            }
+            actionspace.push(CompanyAction::CancelOrders(i));
+            actionspace.push(CompanyAction::CancelOffers(i));
+            for amount in definition.quantities.iter().copied() {
+                for price in definition.price_levels.iter().copied() {
+                    for maturity in definition.future_maturities.iter().copied() {
+                        actionspace.push(CompanyAction::BuyFuture(i, amount, price, maturity));
+                        actionspace.push(CompanyAction::SellFuture(i, amount, price, maturity));
+                    }
+                }
+            }
+            for position in [Position::Long, Position::Short] {
+                for amount in definition.quantities.iter().copied() {
+                    for price in definition.price_levels.iter().copied() {
+                        for interval in definition.supply_intervals.iter().copied() {
+                            for deliveries in definition.supply_deliveries.iter().copied() {
+                                actionspace.push(CompanyAction::ProposeSupplyContract(
+                                    i, amount, price, interval, deliveries, position,
+                                ));
+                            }
+                        }
+                    }
+                }
+                actionspace.push(CompanyAction::AcceptSupplyContract(i, position));
+            }
+        }
+        let hybrid = if definition.hybrid {
+            Some(HybridActionSpace::new(definition, &actionspace))
+        } else {
+            None
+        };
         ActionSpace {
             actions: actionspace,
+            definition: definition.clone(),
+            resources,
+            labor_resource,
+            hybrid,
+        }
+    }
+
+    // Exports to every region, companies can not export to their own region
+    pub fn add_export_actions(&mut self, region_count: usize) {
+        for region in 0..region_count {
+            for i in self.resources.iter().copied() {
+                for amount in self.definition.quantities.iter().copied() {
+                    for price in self.definition.price_levels.iter().copied() {
+                        self.actions
+                            .push(CompanyAction::ExportResource(i, amount, price, region));
+                    }
+                }
+            }
+        }
+    }
+
+    // Groups to choose one action from each tick, every group contains a way of doing nothing
+    pub fn get_action_groups(&self) -> Vec<Vec<usize>> {
+        let all_actions: Vec<usize> = (0..self.actions.len()).collect();
+        match self.definition.action_mode {
+            ActionMode::Single => vec![all_actions],
+            ActionMode::Multiple(action_count) => vec![all_actions; action_count],
+            ActionMode::Bundle => {
+                let mut capital_actions: Vec<usize> = vec![];
+                let mut resource_actions: Vec<Vec<usize>> =
+                    self.resources.iter().map(|_| vec![]).collect();
+                for (index, action) in self.actions.iter().enumerate() {
+                    match get_action_resource(action)
+                        .and_then(|resource| self.resources.iter().position(|r| *r == resource))
+                    {
+                        Some(group) => resource_actions[group].push(index),
+                        None => capital_actions.push(index),
+                    }
+                }
+                let mut groups = vec![capital_actions];
+                groups.append(&mut resource_actions);
+                groups
+            }
         }
     }
+
+    // Resource of each action group, groups without one are credited with the rest of the reward
+    pub fn get_group_resources(&self) -> Vec<Option<ResourceHandle>> {
+        match self.definition.action_mode {
+            ActionMode::Single => vec![None],
+            ActionMode::Multiple(action_count) => vec![None; action_count],
+            ActionMode::Bundle => std::iter::once(None)
+                .chain(self.resources.iter().copied().map(Some))
+                .collect(),
+        }
+    }
+
+    // Actions which are not rejected right away given the company's current state
+    pub fn get_action_mask(
+        &self,
+        company: &Company,
+        market_data: &MarketData,
+        processor_data: &ProcessorData,
+        storage_data: &StorageData,
+    ) -> Vec<bool> {
+        let has_open_order = |resource: ResourceHandle| {
+            company
+                .open_orders
+                .iter()
+                .any(|order_handle| market_data.orders[order_handle].resource == resource)
+        };
+        let has_open_offer = |resource: ResourceHandle| {
+            company
+                .open_offers
+                .iter()
+                .any(|offer_handle| market_data.offers[offer_handle].resource == resource)
+        };
+        let can_buy =
+            |resource: ResourceHandle, amount: usize, price_percentage: usize, reference| {
+                market_data
+                    .get_reference_price(resource, reference)
+                    .map(|price| {
+                        company.currency >= amount as f64 * price * price_percentage as f64 / 100.0
+                    })
+                    .unwrap_or(false)
+            };
+        let can_sell = |resource: ResourceHandle, amount: usize| {
+            company.stock.get_amount(resource) >= amount as f64
+        };
+        self.actions
+            .iter()
+            .map(|action| match *action {
+                CompanyAction::Nothing | CompanyAction::NothingWithResource(_) => true,
+                CompanyAction::BuyProcessor(recipe) => {
+                    company.currency >= processor_data.get_processor_price(recipe)
+                }
+                CompanyAction::SellProcessor(processor)
+                | CompanyAction::OfferProcessor(processor, _) => {
+                    processor < company.processors.len()
+                }
+                CompanyAction::SellProcessorOfRecipe(recipe)
+                | CompanyAction::OfferProcessorOfRecipe(recipe, _) => company
+                    .processors
+                    .iter()
+                    .any(|processor| processor.recipe == recipe),
+                CompanyAction::BuyUsedProcessor(recipe) => market_data
+                    .get_cheapest_processor_offer(recipe, Some(company.id))
+                    .map(|(_, price)| company.currency >= price)
+                    .unwrap_or(false),
+                CompanyAction::BuyWarehouse => company.currency >= storage_data.warehouse_price,
+                CompanyAction::Hire(_, _) => true,
+                CompanyAction::Fire(_) => company.employees > 0.0,
+                CompanyAction::BuyResource(_, amount, max_price)
+                | CompanyAction::BuyResourceWithType(_, amount, max_price, _) => {
+                    company.currency >= (amount * max_price) as f64
+                }
+                CompanyAction::SellResource(resource, amount, _)
+                | CompanyAction::SellResourceWithType(resource, amount, _, _) => {
+                    can_sell(resource, amount)
+                }
+                CompanyAction::BuyResourceRelative(
+                    resource,
+                    amount,
+                    price_percentage,
+                    reference,
+                ) => can_buy(resource, amount, price_percentage, reference),
+                CompanyAction::SellResourceRelative(resource, amount, _, reference) => {
+                    can_sell(resource, amount)
+                        && market_data
+                            .get_reference_price(resource, reference)
+                            .is_some()
+                }
+                CompanyAction::CancelOrders(resource)
+                | CompanyAction::AmendOrders(resource, _)
+                | CompanyAction::AmendOrderAmounts(resource, _) => has_open_order(resource),
+                CompanyAction::CancelOffers(resource)
+                | CompanyAction::AmendOffers(resource, _)
+                | CompanyAction::AmendOfferAmounts(resource, _) => has_open_offer(resource),
+                CompanyAction::ExportResource(resource, amount, _, region) => {
+                    region != company.region && can_sell(resource, amount)
+                }
+                // Margins and deliveries are checked when the contracts are processed
+                CompanyAction::BuyFuture(..)
+                | CompanyAction::SellFuture(..)
+                | CompanyAction::ProposeSupplyContract(..)
+                | CompanyAction::AcceptSupplyContract(..) => company.currency > 0.0,
+                CompanyAction::Parameterized(ParameterizedAction::BuyResource(resource)) => {
+                    company.currency > 0.0
+                        && market_data
+                            .get_reference_price(resource, self.definition.buy_reference)
+                            .is_some()
+                }
+                CompanyAction::Parameterized(ParameterizedAction::SellResource(resource)) => {
+                    can_sell(resource, 1)
+                        && market_data
+                            .get_reference_price(resource, self.definition.sell_reference)
+                            .is_some()
+                }
+            })
+            .collect()
+    }
+}
+
+// Resource an action trades, actions without one affect the company's capital
+pub fn get_action_resource(action: &CompanyAction) -> Option<ResourceHandle> {
+    match *action {
+        CompanyAction::NothingWithResource(resource)
+        | CompanyAction::BuyResource(resource, ..)
+        | CompanyAction::SellResource(resource, ..)
+        | CompanyAction::BuyResourceRelative(resource, ..)
+        | CompanyAction::SellResourceRelative(resource, ..)
+        | CompanyAction::BuyResourceWithType(resource, ..)
+        | CompanyAction::SellResourceWithType(resource, ..)
+        | CompanyAction::CancelOrders(resource)
+        | CompanyAction::CancelOffers(resource)
+        | CompanyAction::AmendOrders(resource, _)
+        | CompanyAction::AmendOffers(resource, _)
+        | CompanyAction::AmendOrderAmounts(resource, _)
+        | CompanyAction::AmendOfferAmounts(resource, _)
+        | CompanyAction::ExportResource(resource, ..)
+        | CompanyAction::BuyFuture(resource, ..)
+        | CompanyAction::SellFuture(resource, ..)
+        | CompanyAction::ProposeSupplyContract(resource, ..)
+        | CompanyAction::AcceptSupplyContract(resource, _)
+        | CompanyAction::Parameterized(ParameterizedAction::BuyResource(resource))
+        | CompanyAction::Parameterized(ParameterizedAction::SellResource(resource)) => {
+            Some(resource)
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn count_actions(actionspace: &ActionSpace, filter: impl Fn(&CompanyAction) -> bool) -> usize {
+        actionspace
+            .actions
+            .iter()
+            .filter(|action| filter(action))
+            .count()
+    }
+
+    #[test]
+    fn market_futures_and_supply_grids_follow_definition() {
+        let definition = ActionSpaceDefinition {
+            quantities: vec![1, 5],
+            price_levels: vec![2, 8, 32],
+            future_maturities: vec![1, 3],
+            supply_intervals: vec![5],
+            supply_deliveries: vec![4, 8],
+            ..ActionSpaceDefinition::new()
+        };
+        let actionspace = ActionSpace::new(&definition, 2, 0, None);
+        let market_buys = count_actions(&actionspace, |action| {
+            matches!(
+                action,
+                CompanyAction::BuyResourceWithType(_, _, _, OrderType::Market)
+            )
+        });
+        let market_sells = count_actions(&actionspace, |action| {
+            matches!(
+                action,
+                CompanyAction::SellResourceWithType(_, _, 0, OrderType::Market)
+            )
+        });
+        let futures = count_actions(&actionspace, |action| {
+            matches!(action, CompanyAction::BuyFuture(..))
+        });
+        let supply_contracts = count_actions(&actionspace, |action| {
+            matches!(action, CompanyAction::ProposeSupplyContract(..))
+        });
+        assert_eq!(market_buys, 2 * 2 * 3);
+        assert_eq!(market_sells, 2 * 2);
+        assert_eq!(futures, 2 * 2 * 3 * 2);
+        assert_eq!(supply_contracts, 2 * 2 * 2 * 3 * 2);
+        assert!(actionspace
+            .actions
+            .contains(&CompanyAction::ProposeSupplyContract(
+                1,
+                5,
+                32,
+                5,
+                8,
+                Position::Short
+            )));
+        assert!(actionspace.hybrid.is_none());
+    }
+
+    #[test]
+    fn hybrid_action_space_covers_parameterized_actions() {
+        let definition = ActionSpaceDefinition {
+            quantities: vec![1, 20],
+            relative_prices: vec![90, 110],
+            hybrid: true,
+            ..ActionSpaceDefinition::new()
+        };
+        let actionspace = ActionSpace::new(&definition, 3, 1, Some(2));
+        let hybrid = actionspace.hybrid.as_ref().unwrap();
+        let parameterized: Vec<&CompanyAction> = hybrid
+            .parameterized_actions
+            .iter()
+            .map(|action| &actionspace.actions[*action])
+            .collect();
+        // Labor is traded through hiring, not with parameterized actions
+        assert!(
+            parameterized
+                == vec![
+                    &CompanyAction::Parameterized(ParameterizedAction::BuyResource(0)),
+                    &CompanyAction::Parameterized(ParameterizedAction::SellResource(0)),
+                    &CompanyAction::Parameterized(ParameterizedAction::BuyResource(1)),
+                    &CompanyAction::Parameterized(ParameterizedAction::SellResource(1)),
+                ]
+        );
+        assert_eq!(hybrid.get_parameters(50.0, 50.0), (20.0, 90.0));
+        assert_eq!(hybrid.get_default_parameters(), (10.5, 100.0));
+    }
 }
@@ -0,0 +1,365 @@
+use crate::reinforcement_learning::action::{HybridAction, HybridActionSpace};
+use crate::reinforcement_learning::controller::choose_group_actions;
+use crate::reinforcement_learning::state::CompanyState;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Standard deviation of the continuous parameters before they are squashed into their ranges
+const PARAMETER_DEVIATION: f64 = 0.5;
+
+// Fully connected network with one tanh hidden layer and a linear output layer
+#[derive(Serialize, Deserialize, Clone)]
+struct DenseNetwork {
+    input_size: usize,
+    hidden_size: usize,
+    output_size: usize,
+    hidden_weights: Vec<f64>,
+    hidden_biases: Vec<f64>,
+    output_weights: Vec<f64>,
+    output_biases: Vec<f64>,
+}
+
+impl DenseNetwork {
+    fn new(input_size: usize, hidden_size: usize, output_size: usize) -> DenseNetwork {
+        let mut rng = rand::thread_rng();
+        let mut init = |fan_in: usize, fan_out: usize| -> Vec<f64> {
+            let limit = (6.0 / (fan_in + fan_out) as f64).sqrt();
+            (0..fan_in * fan_out)
+                .map(|_| rng.gen_range(-limit..limit))
+                .collect()
+        };
+        DenseNetwork {
+            input_size,
+            hidden_size,
+            output_size,
+            hidden_weights: init(input_size, hidden_size),
+            hidden_biases: vec![0.0; hidden_size],
+            output_weights: init(hidden_size, output_size),
+            output_biases: vec![0.0; output_size],
+        }
+    }
+
+    fn get_hidden(&self, input: &[f64]) -> Vec<f64> {
+        (0..self.hidden_size)
+            .map(|j| {
+                let weights = &self.hidden_weights[j * self.input_size..(j + 1) * self.input_size];
+                let sum: f64 = weights.iter().zip(input).map(|(w, x)| w * x).sum();
+                (sum + self.hidden_biases[j]).tanh()
+            })
+            .collect()
+    }
+
+    fn get_output(&self, hidden: &[f64], output: usize) -> f64 {
+        let weights =
+            &self.output_weights[output * self.hidden_size..(output + 1) * self.hidden_size];
+        let sum: f64 = weights.iter().zip(hidden).map(|(w, h)| w * h).sum();
+        sum + self.output_biases[output]
+    }
+
+    // Gradient descent step for the loss gradients of a sparse set of outputs
+    fn backward(
+        &mut self,
+        input: &[f64],
+        hidden: &[f64],
+        output_gradients: &[(usize, f64)],
+        learning_rate: f64,
+    ) {
+        let mut hidden_gradients = vec![0.0; self.hidden_size];
+        for (output, gradient) in output_gradients.iter() {
+            let weights = &mut self.output_weights
+                [output * self.hidden_size..(output + 1) * self.hidden_size];
+            for j in 0..self.hidden_size {
+                hidden_gradients[j] += weights[j] * gradient;
+                weights[j] -= learning_rate * gradient * hidden[j];
+            }
+            self.output_biases[*output] -= learning_rate * gradient;
+        }
+        for j in 0..self.hidden_size {
+            let gradient = hidden_gradients[j] * (1.0 - hidden[j] * hidden[j]);
+            let weights = &mut self.hidden_weights[j * self.input_size..(j + 1) * self.input_size];
+            for (weight, x) in weights.iter_mut().zip(input) {
+                *weight -= learning_rate * gradient * x;
+            }
+            self.hidden_biases[j] -= learning_rate * gradient;
+        }
+    }
+}
+
+// Softmax over the given actions only
+fn get_policy(actor: &DenseNetwork, hidden: &[f64], actions: &[usize]) -> Vec<f64> {
+    let logits: Vec<f64> = actions
+        .iter()
+        .map(|action| actor.get_output(hidden, *action))
+        .collect();
+    let max_logit = logits.iter().copied().fold(f64::MIN, f64::max);
+    let exps: Vec<f64> = logits
+        .iter()
+        .map(|logit| (logit - max_logit).exp())
+        .collect();
+    let sum: f64 = exps.iter().sum();
+    exps.iter().map(|exp| exp / sum).collect()
+}
+
+// Maps an unbounded parameter into its range
+fn squash(parameter: f64, range: (f64, f64)) -> f64 {
+    range.0 + (range.1 - range.0) / (1.0 + (-parameter).exp())
+}
+
+// Standard normal sample using the Box-Muller transform
+fn sample_standard_normal<R: Rng>(rng: &mut R) -> f64 {
+    let u1: f64 = rng.gen::<f64>().max(1e-12);
+    let u2: f64 = rng.gen();
+    (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos()
+}
+
+#[derive(Clone)]
+struct Transition {
+    features: Vec<f64>,
+    actions: Vec<usize>,
+    // Actions the policy chose from in each group
+    available_actions: Vec<Vec<usize>>,
+    // Unsquashed amount and price sampled for the parameterized actions of each group
+    parameters: Vec<Option<(f64, f64)>>,
+    reward: f64,
+}
+
+// Advantage actor-critic with generalized advantage estimation and an entropy bonus
+#[derive(Serialize, Deserialize)]
+pub struct ActorCriticAgent {
+    actor: DenseNetwork,
+    critic: DenseNetwork,
+    // Means of the amount and price of every action, only used for parameterized actions
+    #[serde(default)]
+    parameters: Option<Box<DenseNetwork>>,
+    discount: f64,
+    gae_lambda: f64,
+    entropy_coefficient: f64,
+    actor_learning_rate: f64,
+    critic_learning_rate: f64,
+    rollout_length: usize,
+    // Running mean of absolute rewards, rewards are divided by it
+    reward_scale: f64,
+    #[serde(skip)]
+    rollout: Vec<Transition>,
+    #[serde(default)]
+    last_actions: Vec<usize>,
+    #[serde(default)]
+    last_available_actions: Vec<Vec<usize>>,
+    #[serde(default)]
+    last_parameters: Vec<Option<(f64, f64)>>,
+    // Number of last choices which would have been invalid without the action mask
+    #[serde(default)]
+    pub last_invalid_choices: usize,
+}
+
+impl ActorCriticAgent {
+    pub fn new(state_dimensions: i32, action_dimensions: i32, discount: f64) -> ActorCriticAgent {
+        let state_dimensions = state_dimensions as usize;
+        ActorCriticAgent {
+            actor: DenseNetwork::new(state_dimensions, 64, action_dimensions as usize),
+            critic: DenseNetwork::new(state_dimensions, 64, 1),
+            parameters: Some(Box::new(DenseNetwork::new(
+                state_dimensions,
+                64,
+                2 * action_dimensions as usize,
+            ))),
+            discount,
+            gae_lambda: 0.95,
+            entropy_coefficient: 0.01,
+            actor_learning_rate: 0.001,
+            critic_learning_rate: 0.005,
+            rollout_length: 32,
+            reward_scale: 1.0,
+            rollout: vec![],
+            last_actions: vec![],
+            last_available_actions: vec![],
+            last_parameters: vec![],
+            last_invalid_choices: 0,
+        }
+    }
+
+    // Currency and prices span several orders of magnitude
+    fn get_features(state: &CompanyState) -> Vec<f64> {
+        state.as_f64_vec().iter().map(|x| x.ln_1p()).collect()
+    }
+
+    fn get_value(&self, features: &[f64]) -> f64 {
+        self.critic.get_output(&self.critic.get_hidden(features), 0)
+    }
+
+    fn sample<R: Rng>(
+        &self,
+        hidden: &[f64],
+        actions: &[usize],
+        exploration_factor: f64,
+        rng: &mut R,
+    ) -> usize {
+        if exploration_factor > rng.gen() {
+            return *actions.choose(rng).unwrap();
+        }
+        let policy = get_policy(&self.actor, hidden, actions);
+        let mut threshold: f64 = rng.gen();
+        for (action, probability) in actions.iter().zip(policy.iter()) {
+            threshold -= probability;
+            if threshold <= 0.0 {
+                return *action;
+            }
+        }
+        *actions.last().unwrap()
+    }
+
+    // Samples from the policy, which is restricted to the actions a group's choice is made from.
+    // Amounts and prices of parameterized actions are sampled from a Gaussian around the
+    // parameter network's outputs.
+    pub fn get_next_state_actions<R: Rng>(
+        &mut self,
+        state: &CompanyState,
+        exploration_factor: f64,
+        action_mask: &[bool],
+        action_groups: &[Vec<usize>],
+        hybrid: Option<&HybridActionSpace>,
+        rng: &mut R,
+    ) -> Vec<HybridAction> {
+        let features = ActorCriticAgent::get_features(state);
+        let hidden = self.actor.get_hidden(&features);
+        let choice = choose_group_actions(action_mask, action_groups, |_, actions| {
+            self.sample(&hidden, actions, exploration_factor, rng)
+        });
+        let mut hybrid_actions = vec![];
+        self.last_parameters = vec![];
+        for action in choice.actions.iter().copied() {
+            match (hybrid, &self.parameters) {
+                (Some(hybrid), Some(parameters)) if hybrid.is_parameterized(action) => {
+                    let hidden = parameters.get_hidden(&features);
+                    let amount = parameters.get_output(&hidden, 2 * action)
+                        + PARAMETER_DEVIATION * sample_standard_normal(rng);
+                    let price = parameters.get_output(&hidden, 2 * action + 1)
+                        + PARAMETER_DEVIATION * sample_standard_normal(rng);
+                    self.last_parameters.push(Some((amount, price)));
+                    hybrid_actions.push(HybridAction::Parameterized {
+                        action,
+                        amount: squash(amount, hybrid.amount_range),
+                        price_percentage: squash(price, hybrid.price_range),
+                    });
+                }
+                _ => {
+                    self.last_parameters.push(None);
+                    hybrid_actions.push(HybridAction::Discrete(action));
+                }
+            }
+        }
+        self.last_invalid_choices = choice.invalid_choices;
+        self.last_actions = choice.actions;
+        self.last_available_actions = choice.available_actions;
+        hybrid_actions
+    }
+
+    // Collects transitions and updates both networks once a rollout is complete
+    pub fn train(&mut self, old_state: &CompanyState, reward: f64, new_state: &CompanyState) {
+        if self.last_actions.is_empty() {
+            return;
+        }
+        self.reward_scale = 0.99 * self.reward_scale + 0.01 * reward.abs().max(1e-6);
+        self.rollout.push(Transition {
+            features: ActorCriticAgent::get_features(old_state),
+            actions: self.last_actions.clone(),
+            available_actions: self.last_available_actions.clone(),
+            parameters: self.last_parameters.clone(),
+            reward: reward / self.reward_scale,
+        });
+        if self.rollout.len() >= self.rollout_length {
+            let bootstrap_value = self.get_value(&ActorCriticAgent::get_features(new_state));
+            self.update(bootstrap_value);
+        }
+    }
+
+    fn update(&mut self, bootstrap_value: f64) {
+        let rollout = std::mem::take(&mut self.rollout);
+        let values: Vec<f64> = rollout
+            .iter()
+            .map(|transition| self.get_value(&transition.features))
+            .collect();
+        // Generalized advantage estimation
+        let mut advantages = vec![0.0; rollout.len()];
+        let mut advantage = 0.0;
+        let mut next_value = bootstrap_value;
+        for t in (0..rollout.len()).rev() {
+            let delta = rollout[t].reward + self.discount * next_value - values[t];
+            advantage = delta + self.discount * self.gae_lambda * advantage;
+            advantages[t] = advantage;
+            next_value = values[t];
+        }
+        let returns: Vec<f64> = advantages
+            .iter()
+            .zip(values.iter())
+            .map(|(advantage, value)| advantage + value)
+            .collect();
+        let mean = advantages.iter().sum::<f64>() / advantages.len() as f64;
+        let std = (advantages.iter().map(|a| (a - mean).powi(2)).sum::<f64>()
+            / advantages.len() as f64)
+            .sqrt()
+            .max(1e-6);
+        for (t, transition) in rollout.iter().enumerate() {
+            let advantage = (advantages[t] - mean) / std;
+            // Actor: policy gradient with entropy regularization per group
+            let hidden = self.actor.get_hidden(&transition.features);
+            let mut output_gradients: Vec<(usize, f64)> = vec![];
+            for (action, group_actions) in transition
+                .actions
+                .iter()
+                .zip(transition.available_actions.iter())
+            {
+                let policy = get_policy(&self.actor, &hidden, group_actions);
+                let entropy: f64 = policy.iter().map(|p| -p * p.max(1e-12).ln()).sum();
+                for (candidate, probability) in group_actions.iter().zip(policy.iter()) {
+                    let indicator = if candidate == action { 1.0 } else { 0.0 };
+                    let gradient = advantage * (probability - indicator)
+                        + self.entropy_coefficient
+                            * probability
+                            * (probability.max(1e-12).ln() + entropy);
+                    output_gradients.push((*candidate, gradient));
+                }
+            }
+            self.actor.backward(
+                &transition.features,
+                &hidden,
+                &output_gradients,
+                self.actor_learning_rate,
+            );
+            // Parameters: Gaussian policy gradient towards the sampled amounts and prices
+            if let Some(parameters) = self.parameters.as_mut() {
+                let hidden = parameters.get_hidden(&transition.features);
+                let mut output_gradients: Vec<(usize, f64)> = vec![];
+                for (action, sampled) in transition.actions.iter().zip(transition.parameters.iter())
+                {
+                    if let Some((amount, price)) = sampled {
+                        for (output, sample) in [(2 * action, amount), (2 * action + 1, price)] {
+                            let mean = parameters.get_output(&hidden, output);
+                            output_gradients.push((
+                                output,
+                                -advantage * (sample - mean) / PARAMETER_DEVIATION.powi(2),
+                            ));
+                        }
+                    }
+                }
+                if !output_gradients.is_empty() {
+                    parameters.backward(
+                        &transition.features,
+                        &hidden,
+                        &output_gradients,
+                        self.actor_learning_rate,
+                    );
+                }
+            }
+            // Critic: squared error towards the estimated returns
+            let hidden = self.critic.get_hidden(&transition.features);
+            let value = self.critic.get_output(&hidden, 0);
+            self.critic.backward(
+                &transition.features,
+                &hidden,
+                &[(0, value - returns[t])],
+                self.critic_learning_rate,
+            );
+        }
+    }
+}
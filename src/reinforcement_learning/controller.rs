@@ -0,0 +1,252 @@
+use crate::reinforcement_learning::action::{HybridAction, HybridActionSpace};
+use crate::reinforcement_learning::actor_critic_agent::ActorCriticAgent;
+use crate::reinforcement_learning::deep_rl_agent::DeepRLAgent;
+use crate::reinforcement_learning::heuristic::HeuristicStrategy;
+use crate::reinforcement_learning::q_learning_agent::QLearningAgent;
+use crate::reinforcement_learning::state::CompanyState;
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default, Debug)]
+pub enum ControllerType {
+    // Neural network approximating the Q values of all actions
+    #[default]
+    DeepRL,
+    // Q table over bucketed company states, suited for small worlds
+    QLearning,
+    // Stochastic policy trained with advantage actor-critic
+    ActorCritic,
+    // Scripted baseline which does not learn
+    Heuristic(HeuristicStrategy),
+}
+
+// Actions chosen for one tick together with the actions each group's choice was made from
+pub struct GroupChoice {
+    pub actions: Vec<usize>,
+    pub available_actions: Vec<Vec<usize>>,
+    // Number of choices which would have been invalid without the action mask
+    pub invalid_choices: usize,
+}
+
+// Chooses one action per group among the valid actions, an empty mask allows all actions.
+// Actions other than doing nothing are chosen at most once. The agent chooses from the whole
+// group first and again from the available actions if its first choice is not available.
+pub fn choose_group_actions(
+    action_mask: &[bool],
+    action_groups: &[Vec<usize>],
+    mut choose: impl FnMut(usize, &[usize]) -> usize,
+) -> GroupChoice {
+    let is_valid = |action: usize| action_mask.get(action).copied().unwrap_or(true);
+    let mut choice = GroupChoice {
+        actions: vec![],
+        available_actions: vec![],
+        invalid_choices: 0,
+    };
+    for (group_index, group) in action_groups.iter().enumerate() {
+        let unmasked_action = choose(group_index, group);
+        if !is_valid(unmasked_action) {
+            choice.invalid_choices += 1;
+        }
+        let available_actions: Vec<usize> = group
+            .iter()
+            .copied()
+            .filter(|action| {
+                is_valid(*action) && (*action == 0 || !choice.actions.contains(action))
+            })
+            .collect();
+        let action = if available_actions.is_empty() || available_actions.contains(&unmasked_action)
+        {
+            unmasked_action
+        } else {
+            choose(group_index, &available_actions)
+        };
+        choice.actions.push(action);
+        choice
+            .available_actions
+            .push(if available_actions.is_empty() {
+                vec![action]
+            } else {
+                available_actions
+            });
+    }
+    choice
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum CompanyController {
+    DeepRL(DeepRLAgent),
+    QLearning(QLearningAgent),
+    ActorCritic(ActorCriticAgent),
+    Heuristic(HeuristicStrategy),
+}
+
+// Worlds saved before controllers were tagged hold a bare neural network agent
+#[derive(Deserialize)]
+#[serde(untagged)]
+#[allow(clippy::large_enum_variant)]
+enum StoredController {
+    Tagged(CompanyController),
+    Legacy(DeepRLAgent),
+}
+
+impl CompanyController {
+    pub fn deserialize_stored<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<CompanyController, D::Error> {
+        Ok(match StoredController::deserialize(deserializer)? {
+            StoredController::Tagged(controller) => controller,
+            StoredController::Legacy(agent) => CompanyController::DeepRL(agent),
+        })
+    }
+
+    pub fn new(
+        controller_type: ControllerType,
+        state_dimensions: i32,
+        action_dimensions: i32,
+        discount: f64,
+    ) -> CompanyController {
+        match controller_type {
+            ControllerType::DeepRL => CompanyController::DeepRL(DeepRLAgent::new(
+                state_dimensions,
+                action_dimensions,
+                discount,
+            )),
+            ControllerType::QLearning => {
+                CompanyController::QLearning(QLearningAgent::new(discount))
+            }
+            ControllerType::ActorCritic => CompanyController::ActorCritic(ActorCriticAgent::new(
+                state_dimensions,
+                action_dimensions,
+                discount,
+            )),
+            ControllerType::Heuristic(strategy) => CompanyController::Heuristic(strategy),
+        }
+    }
+
+    pub fn get_name(&self) -> String {
+        match self {
+            CompanyController::DeepRL(_) => String::from("DeepRL"),
+            CompanyController::QLearning(_) => String::from("QLearning"),
+            CompanyController::ActorCritic(_) => String::from("ActorCritic"),
+            CompanyController::Heuristic(strategy) => format!("{:?}", strategy),
+        }
+    }
+
+    // Rewards are credited per action group, the actor-critic agent learns from their sum
+    pub fn train(
+        &mut self,
+        old_state: &CompanyState,
+        group_rewards: &[f64],
+        new_state: &CompanyState,
+        action_groups: &[Vec<usize>],
+    ) {
+        match self {
+            CompanyController::DeepRL(agent) => agent.train(
+                old_state.as_f64_vec(),
+                group_rewards,
+                new_state.as_f64_vec(),
+                action_groups,
+            ),
+            CompanyController::QLearning(agent) => {
+                agent.train(old_state, group_rewards, new_state, action_groups)
+            }
+            CompanyController::ActorCritic(agent) => agent.train(
+                old_state,
+                group_rewards.iter().fold(0.0, |a, b| a + b),
+                new_state,
+            ),
+            CompanyController::Heuristic(_) => {}
+        }
+    }
+
+    // Only the actor-critic agent chooses continuous parameters of the parameterized actions
+    pub fn get_next_state_actions<R: Rng>(
+        &mut self,
+        state: &CompanyState,
+        exploration_factor: f64,
+        action_mask: &[bool],
+        action_groups: &[Vec<usize>],
+        hybrid: Option<&HybridActionSpace>,
+        rng: &mut R,
+    ) -> Vec<HybridAction> {
+        let actions = match self {
+            CompanyController::DeepRL(agent) => agent.get_next_state_actions(
+                state.as_f64_vec(),
+                exploration_factor,
+                action_mask,
+                action_groups,
+                rng,
+            ),
+            CompanyController::QLearning(agent) => agent.get_next_state_actions(
+                state,
+                exploration_factor,
+                action_mask,
+                action_groups,
+                rng,
+            ),
+            CompanyController::ActorCritic(agent) => {
+                return agent.get_next_state_actions(
+                    state,
+                    exploration_factor,
+                    action_mask,
+                    action_groups,
+                    hybrid,
+                    rng,
+                )
+            }
+            // Heuristic strategies need the market context and are queried by the company
+            CompanyController::Heuristic(_) => action_groups.iter().map(|_| 0).collect(),
+        };
+        actions.into_iter().map(HybridAction::Discrete).collect()
+    }
+
+    pub fn get_last_invalid_choices(&self) -> usize {
+        match self {
+            CompanyController::DeepRL(agent) => agent.last_invalid_choices,
+            CompanyController::QLearning(agent) => agent.last_invalid_choices,
+            CompanyController::ActorCritic(agent) => agent.last_invalid_choices,
+            CompanyController::Heuristic(_) => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct StoredCompany {
+        #[serde(deserialize_with = "CompanyController::deserialize_stored")]
+        agent: CompanyController,
+    }
+
+    fn reload(agent: CompanyController) -> String {
+        let yaml = serde_yaml::to_string(&StoredCompany { agent }).unwrap();
+        let company: StoredCompany = serde_yaml::from_str(&yaml).unwrap();
+        company.agent.get_name()
+    }
+
+    #[test]
+    fn keeps_controller_type_when_reloaded() {
+        let controller_types = [
+            ControllerType::DeepRL,
+            ControllerType::QLearning,
+            ControllerType::ActorCritic,
+            ControllerType::Heuristic(HeuristicStrategy::MarketMaker),
+        ];
+        for controller_type in controller_types.iter() {
+            let agent = CompanyController::new(*controller_type, 4, 3, 0.9);
+            let name = agent.get_name();
+            assert_eq!(reload(agent), name);
+        }
+    }
+
+    #[test]
+    fn loads_bare_neural_network_agent() {
+        let yaml = serde_yaml::to_string(&DeepRLAgent::new(4, 3, 0.9)).unwrap();
+        let agent =
+            CompanyController::deserialize_stored(serde_yaml::Deserializer::from_str(&yaml))
+                .unwrap();
+        assert!(matches!(agent, CompanyController::DeepRL(_)));
+    }
+}
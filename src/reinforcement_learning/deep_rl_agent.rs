@@ -1,3 +1,4 @@
+use crate::reinforcement_learning::controller::choose_group_actions;
 use neuroflow::activators::Type::Relu;
 use neuroflow::FeedForward;
 use rand::prelude::*;
@@ -8,7 +9,12 @@ pub struct DeepRLAgent {
     pub neural_network: FeedForward,
     action_dimensions: usize,
     discount: f64,
-    last_action: usize,
+    // Actions taken in the last tick, one per action group
+    #[serde(default)]
+    last_actions: Vec<usize>,
+    // Number of last choices which would have been invalid without the action mask
+    #[serde(default)]
+    pub last_invalid_choices: usize,
 }
 
 impl DeepRLAgent {
@@ -22,35 +28,56 @@ impl DeepRLAgent {
         neural_network.learning_rate(0.1);
         neural_network.activation(Relu);
         DeepRLAgent {
-            neural_network: neural_network,
+            neural_network,
             action_dimensions: action_dimensions as usize,
-            discount: discount,
-            last_action: 0,
+            discount,
+            last_actions: vec![],
+            last_invalid_choices: 0,
         }
     }
 
-    pub fn get_next_state_action(&mut self, state: Vec<f64>, exploration_factor: f64) -> usize {
-        let mut rng = rand::thread_rng();
-        if exploration_factor > rng.gen() {
-            rng.next_u64() as usize % self.action_dimensions
-        } else {
-            let index_of_max: Option<usize> = self
-                .neural_network
-                .calc(&state)
-                .iter()
-                .enumerate()
-                .max_by(|(_, a), (_, b)| a.total_cmp(b))
-                .map(|(index, _)| index);
-            self.last_action = index_of_max.unwrap();
-            self.last_action
-        }
+    // Explores uniformly, otherwise takes the action with the highest Q value
+    pub fn get_next_state_actions<R: Rng>(
+        &mut self,
+        state: Vec<f64>,
+        exploration_factor: f64,
+        action_mask: &[bool],
+        action_groups: &[Vec<usize>],
+        rng: &mut R,
+    ) -> Vec<usize> {
+        let q_values = self.neural_network.calc(&state).to_vec();
+        let explore: Vec<bool> = action_groups
+            .iter()
+            .map(|_| exploration_factor > rng.gen())
+            .collect();
+        let choice = choose_group_actions(action_mask, action_groups, |group, actions| {
+            if explore[group] {
+                *actions.choose(rng).unwrap()
+            } else {
+                actions
+                    .iter()
+                    .copied()
+                    .max_by(|a, b| q_values[*a].total_cmp(&q_values[*b]))
+                    .unwrap()
+            }
+        });
+        self.last_invalid_choices = choice.invalid_choices;
+        self.last_actions = choice.actions.clone();
+        choice.actions
     }
 
     pub fn get_output(network: &mut FeedForward, state: &[f64]) -> Vec<f64> {
-        network.calc(&state).iter().map(|x| *x).collect()
+        network.calc(state).to_vec()
     }
 
-    pub fn train(&mut self, old_state: Vec<f64>, reward: f64, new_state: Vec<f64>) {
+    // Each action taken in the last tick is credited with the reward of its group
+    pub fn train(
+        &mut self,
+        old_state: Vec<f64>,
+        group_rewards: &[f64],
+        new_state: Vec<f64>,
+        action_groups: &[Vec<usize>],
+    ) {
         // Example implementation
         // https://github.com/valohai/qlearning-simple/blob/master/deep_gambler.py
         // Ask the model for the Q values of the old state (inference)
@@ -59,10 +86,19 @@ impl DeepRLAgent {
         // Ask the model for the Q values of the new state (inference)
         let new_state_q_values = DeepRLAgent::get_output(&mut self.neural_network, &new_state);
 
-        // Real Q value for the action we took. This is what we will train towards.
-        let max_index = self.get_next_state_action(new_state, 0.0);
-        old_state_q_values[self.last_action] =
-            reward + self.discount * new_state_q_values[max_index];
+        // Real Q values for the actions we took. This is what we will train towards.
+        for ((action, group), reward) in self
+            .last_actions
+            .iter()
+            .zip(action_groups.iter())
+            .zip(group_rewards.iter())
+        {
+            let max_q_value = group
+                .iter()
+                .map(|next_action| new_state_q_values[*next_action])
+                .fold(f64::MIN, f64::max);
+            old_state_q_values[*action] = reward + self.discount * max_q_value;
+        }
 
         // Train
         self.neural_network
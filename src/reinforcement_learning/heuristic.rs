@@ -0,0 +1,354 @@
+use crate::analysis::profitability::{PriceVector, RecipeProfitability};
+use crate::economy::company::Company;
+use crate::economy::recipe::RecipeHandle;
+use crate::economy::resource::ResourceHandle;
+use crate::reinforcement_learning::action::{ActionSpace, CompanyAction};
+use crate::world_data::market_data::MarketData;
+use crate::world_data::processor_data::ProcessorData;
+use crate::world_data::recipe_data::RecipeData;
+use serde::{Deserialize, Serialize};
+
+// Number of production cycles a processor has to pay for itself in
+const PAYBACK_CYCLES: f64 = 100.0;
+// Ingredients are only bought up to this many production cycles in advance
+const STOCK_CYCLES: f64 = 20.0;
+// Distance of the market maker's quotes from the mid price
+const MARKET_MAKER_SPREAD: f64 = 0.05;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HeuristicStrategy {
+    // Buys ingredients below the break-even price of its recipes and sells products above cost
+    BreakEvenTrader,
+    // Quotes bids and asks around the mid price
+    MarketMaker,
+    // Buys processors for the most profitable recipe and trades like the break-even trader
+    ProcessorInvestor,
+}
+
+pub struct HeuristicContext<'a> {
+    pub company: &'a Company,
+    pub actionspace: &'a ActionSpace,
+    pub recipe_data: &'a RecipeData,
+    pub market_data: &'a MarketData,
+    pub processor_data: &'a ProcessorData,
+    // Last prices, or market prices for resources which have not been traded yet
+    pub prices: PriceVector,
+}
+
+// Resource, amount and price per unit of a buy or sell action
+struct Listing {
+    resource: ResourceHandle,
+    amount: f64,
+    price: f64,
+    buy: bool,
+}
+
+impl<'a> HeuristicContext<'a> {
+    pub fn new(
+        company: &'a Company,
+        actionspace: &'a ActionSpace,
+        recipe_data: &'a RecipeData,
+        market_data: &'a MarketData,
+        processor_data: &'a ProcessorData,
+    ) -> HeuristicContext<'a> {
+        let prices = PriceVector {
+            prices: (0..market_data.resource_count)
+                .map(|resource| {
+                    market_data.get_last_price(resource).or_else(|| {
+                        let price = market_data.get_market_price(resource);
+                        if price > 0.0 {
+                            Some(price)
+                        } else {
+                            None
+                        }
+                    })
+                })
+                .collect(),
+        };
+        HeuristicContext {
+            company,
+            actionspace,
+            recipe_data,
+            market_data,
+            processor_data,
+            prices,
+        }
+    }
+
+    fn get_price(&self, resource: ResourceHandle) -> Option<f64> {
+        self.prices.get_price(resource)
+    }
+
+    fn get_best_ask(&self, resource: ResourceHandle) -> Option<f64> {
+        self.market_data
+            .price_index
+            .get(&resource)
+            .cloned()
+            .flatten()
+            .map(|(_, price)| price)
+    }
+
+    fn get_best_bid(&self, resource: ResourceHandle) -> Option<f64> {
+        self.market_data
+            .order_index
+            .get(&resource)
+            .cloned()
+            .flatten()
+            .map(|(_, price)| price)
+    }
+
+    fn get_listing(&self, action: &CompanyAction) -> Option<Listing> {
+        let (resource, amount, price, buy) = match *action {
+            CompanyAction::BuyResource(resource, amount, price) => {
+                (resource, amount, Some(price as f64), true)
+            }
+            CompanyAction::SellResource(resource, amount, price) => {
+                (resource, amount, Some(price as f64), false)
+            }
+            CompanyAction::BuyResourceRelative(resource, amount, price_percentage, reference) => (
+                resource,
+                amount,
+                self.market_data
+                    .get_reference_price(resource, reference)
+                    .map(|price| price * price_percentage as f64 / 100.0),
+                true,
+            ),
+            CompanyAction::SellResourceRelative(resource, amount, price_percentage, reference) => (
+                resource,
+                amount,
+                self.market_data
+                    .get_reference_price(resource, reference)
+                    .map(|price| price * price_percentage as f64 / 100.0),
+                false,
+            ),
+            _ => return None,
+        };
+        price.map(|price| Listing {
+            resource,
+            amount: amount as f64,
+            price,
+            buy,
+        })
+    }
+
+    // Whether the listing trades right away against the best opposite listing, if there is any
+    fn would_fill(&self, listing: &Listing) -> bool {
+        if listing.buy {
+            self.get_best_ask(listing.resource)
+                .is_none_or(|ask| listing.price >= ask)
+        } else {
+            self.get_best_bid(listing.resource)
+                .is_none_or(|bid| listing.price <= bid)
+        }
+    }
+
+    // Profitability at current prices, unknown if a price is missing
+    fn get_profitability(&self, recipe: RecipeHandle) -> Option<RecipeProfitability> {
+        let profitability = RecipeProfitability::new(
+            recipe,
+            self.recipe_data.get_recipe_by_handle(recipe)?,
+            self.processor_data,
+            &self.prices,
+        );
+        match profitability.missing_prices.is_empty() {
+            true => Some(profitability),
+            false => None,
+        }
+    }
+
+    fn get_company_recipes(&self) -> Vec<RecipeHandle> {
+        let mut recipes: Vec<RecipeHandle> = self
+            .company
+            .processors
+            .iter()
+            .map(|processor| processor.recipe)
+            .collect();
+        recipes.sort();
+        recipes.dedup();
+        recipes
+    }
+
+    // Highest price per unit of an ingredient at which one of the company's recipes breaks even
+    fn get_break_even_price(&self, resource: ResourceHandle) -> Option<f64> {
+        self.get_company_recipes()
+            .into_iter()
+            .filter_map(|recipe| {
+                self.get_profitability(recipe)?
+                    .break_even_prices
+                    .into_iter()
+                    .find(|(ingredient, _)| *ingredient == resource)
+                    .map(|(_, price)| price)
+            })
+            .max_by(|a, b| a.total_cmp(b))
+    }
+
+    // Lowest costs per unit of a product, costs are split by the products' revenue shares
+    fn get_unit_costs(&self, resource: ResourceHandle) -> Option<f64> {
+        self.get_company_recipes()
+            .into_iter()
+            .filter_map(|recipe| {
+                let amount = *self
+                    .recipe_data
+                    .get_recipe_by_handle(recipe)?
+                    .products
+                    .get(&resource)?;
+                let profitability = self.get_profitability(recipe)?;
+                let share = if profitability.output_value > 0.0 {
+                    self.get_price(resource)? * amount / profitability.output_value
+                } else {
+                    1.0
+                };
+                Some(profitability.input_cost * share / amount)
+            })
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    fn get_required_amount(&self, resource: ResourceHandle) -> f64 {
+        self.company
+            .processors
+            .iter()
+            .filter_map(|processor| self.recipe_data.get_recipe_by_handle(processor.recipe))
+            .filter_map(|recipe| recipe.ingredients.get(&resource))
+            .sum()
+    }
+}
+
+impl HeuristicStrategy {
+    // Chooses the best scoring valid action per group, doing nothing scores zero
+    pub fn get_actions(
+        &self,
+        context: &HeuristicContext,
+        action_mask: &[bool],
+        action_groups: &[Vec<usize>],
+    ) -> Vec<usize> {
+        let scores: Vec<f64> = context
+            .actionspace
+            .actions
+            .iter()
+            .map(|action| self.get_score(context, action).unwrap_or(0.0))
+            .collect();
+        let mut actions: Vec<usize> = vec![];
+        for group in action_groups.iter() {
+            let action = group
+                .iter()
+                .copied()
+                .filter(|action| {
+                    action_mask.get(*action).copied().unwrap_or(true)
+                        && scores[*action] > 0.0
+                        && !actions.contains(action)
+                })
+                .max_by(|a, b| scores[*a].total_cmp(&scores[*b]))
+                .unwrap_or(0);
+            actions.push(action);
+        }
+        actions
+    }
+
+    fn get_score(&self, context: &HeuristicContext, action: &CompanyAction) -> Option<f64> {
+        match self {
+            HeuristicStrategy::BreakEvenTrader => {
+                HeuristicStrategy::get_trader_score(context, action)
+            }
+            HeuristicStrategy::MarketMaker => {
+                HeuristicStrategy::get_market_maker_score(context, action)
+            }
+            HeuristicStrategy::ProcessorInvestor => {
+                HeuristicStrategy::get_investor_score(context, action)
+                    .or_else(|| HeuristicStrategy::get_trader_score(context, action))
+            }
+        }
+    }
+
+    fn get_trader_score(context: &HeuristicContext, action: &CompanyAction) -> Option<f64> {
+        if let CompanyAction::Hire(_, wage) = *action {
+            return HeuristicStrategy::get_hire_score(context, wage as f64);
+        }
+        let listing = context.get_listing(action)?;
+        if !context.would_fill(&listing) {
+            return None;
+        }
+        if listing.buy {
+            let required_amount = context.get_required_amount(listing.resource);
+            if context.company.stock.get_amount(listing.resource) >= required_amount * STOCK_CYCLES
+            {
+                return None;
+            }
+            let break_even_price = context.get_break_even_price(listing.resource)?;
+            if listing.price >= break_even_price {
+                return None;
+            }
+            Some((break_even_price - listing.price) / break_even_price * listing.amount)
+        } else {
+            // Ingredients are kept for production
+            if context.get_required_amount(listing.resource) > 0.0 {
+                return None;
+            }
+            let unit_costs = context.get_unit_costs(listing.resource)?;
+            if listing.price <= unit_costs {
+                return None;
+            }
+            Some((listing.price - unit_costs) / listing.price * listing.amount)
+        }
+    }
+
+    // Hires until the processors' labor demand is covered, at the labor market price
+    fn get_hire_score(context: &HeuristicContext, wage: f64) -> Option<f64> {
+        let labor_resource = context.actionspace.labor_resource?;
+        let required_labor = context.get_required_amount(labor_resource);
+        if context.company.employees >= required_labor {
+            return None;
+        }
+        let labor_price = context.get_price(labor_resource).unwrap_or(1.0);
+        Some(1.0 / (1.0 + (wage - labor_price).abs() / labor_price))
+    }
+
+    fn get_investor_score(context: &HeuristicContext, action: &CompanyAction) -> Option<f64> {
+        let (recipe, price) = match *action {
+            CompanyAction::BuyProcessor(recipe) => {
+                (recipe, context.processor_data.get_processor_price(recipe))
+            }
+            CompanyAction::BuyUsedProcessor(recipe) => (
+                recipe,
+                context
+                    .market_data
+                    .get_cheapest_processor_offer(recipe, Some(context.company.id))?
+                    .1,
+            ),
+            _ => return None,
+        };
+        // Keep enough currency to buy ingredients
+        if context.company.currency < 2.0 * price {
+            return None;
+        }
+        let margin = context.get_profitability(recipe)?.margin;
+        let return_on_investment = margin * PAYBACK_CYCLES / price.max(1.0) - 1.0;
+        // Investments outrank trades
+        if return_on_investment > 0.0 {
+            Some(1000.0 * return_on_investment)
+        } else {
+            None
+        }
+    }
+
+    fn get_market_maker_score(context: &HeuristicContext, action: &CompanyAction) -> Option<f64> {
+        let listing = context.get_listing(action)?;
+        let mid_price = match (
+            context.get_best_bid(listing.resource),
+            context.get_best_ask(listing.resource),
+        ) {
+            (Some(bid), Some(ask)) => (bid + ask) / 2.0,
+            _ => context.get_price(listing.resource)?,
+        };
+        let target_price = if listing.buy {
+            mid_price * (1.0 - MARKET_MAKER_SPREAD)
+        } else {
+            mid_price * (1.0 + MARKET_MAKER_SPREAD)
+        };
+        if (listing.buy && listing.price >= mid_price)
+            || (!listing.buy && listing.price <= mid_price)
+        {
+            return None;
+        }
+        Some(1.0 / (1.0 + (listing.price - target_price).abs() / target_price))
+    }
+}
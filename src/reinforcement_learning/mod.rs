@@ -1,3 +1,7 @@
 pub mod action;
+pub mod actor_critic_agent;
+pub mod controller;
 pub mod deep_rl_agent;
+pub mod heuristic;
+pub mod q_learning_agent;
 pub mod state;
@@ -0,0 +1,201 @@
+use crate::reinforcement_learning::controller::choose_group_actions;
+use crate::reinforcement_learning::state::CompanyState;
+use rand::prelude::*;
+use rurel::mdp::{Agent, State};
+use rurel::strategy::learn::{LearningStrategy, QLearning};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+// Bucketed company state together with the actions of one group available in it and the
+// reward of that group for arriving in the state
+#[derive(Clone, Default)]
+pub struct TabularState {
+    pub state: CompanyState,
+    pub actions: Vec<usize>,
+    pub reward: f64,
+}
+
+impl PartialEq for TabularState {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state
+    }
+}
+
+impl Eq for TabularState {}
+
+impl Hash for TabularState {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        self.state.hash(hasher);
+    }
+}
+
+impl State for TabularState {
+    type A = usize;
+
+    fn reward(&self) -> f64 {
+        self.reward
+    }
+
+    fn actions(&self) -> Vec<usize> {
+        self.actions.clone()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct QLearningAgent {
+    q_values: HashMap<CompanyState, HashMap<usize, f64>>,
+    learning_rate: f64,
+    discount: f64,
+    initial_value: f64,
+    // State of the group whose action is being chosen
+    #[serde(skip)]
+    current_state: TabularState,
+    // Actions taken in the last tick, one per action group
+    #[serde(default)]
+    last_actions: Vec<usize>,
+    // Number of last choices which would have been invalid without the action mask
+    #[serde(default)]
+    pub last_invalid_choices: usize,
+}
+
+impl Agent<TabularState> for QLearningAgent {
+    fn current_state(&self) -> &TabularState {
+        &self.current_state
+    }
+
+    // Chosen actions are executed by the company, the agent remembers them for training
+    fn take_action(&mut self, action: &usize) {
+        self.last_actions.push(*action);
+    }
+}
+
+impl QLearningAgent {
+    pub fn new(discount: f64) -> QLearningAgent {
+        QLearningAgent {
+            q_values: HashMap::new(),
+            learning_rate: 0.1,
+            discount,
+            initial_value: 0.0,
+            current_state: TabularState::default(),
+            last_actions: vec![],
+            last_invalid_choices: 0,
+        }
+    }
+
+    pub fn get_state_count(&self) -> usize {
+        self.q_values.len()
+    }
+
+    pub fn get_q_value(&self, state: &CompanyState, action: usize) -> f64 {
+        self.q_values
+            .get(state)
+            .and_then(|action_values| action_values.get(&action))
+            .copied()
+            .unwrap_or(self.initial_value)
+    }
+
+    // Explores uniformly, otherwise takes the action with the highest Q value, both among the
+    // actions of the current state. Rurel's random exploration can't be seeded.
+    fn pick_action<R: Rng>(&self, explore: bool, rng: &mut R) -> usize {
+        let state = self.current_state();
+        let actions = state.actions();
+        if explore {
+            return actions.choose(rng).copied().unwrap_or(0);
+        }
+        actions
+            .iter()
+            .copied()
+            .max_by(|a, b| {
+                self.get_q_value(&state.state, *a)
+                    .total_cmp(&self.get_q_value(&state.state, *b))
+            })
+            .unwrap_or(0)
+    }
+
+    // Explores uniformly, otherwise takes the action with the highest Q value of the bucketed
+    // state
+    pub fn get_next_state_actions<R: Rng>(
+        &mut self,
+        state: &CompanyState,
+        exploration_factor: f64,
+        action_mask: &[bool],
+        action_groups: &[Vec<usize>],
+        rng: &mut R,
+    ) -> Vec<usize> {
+        let state = state.get_bucketed();
+        let explore: Vec<bool> = action_groups
+            .iter()
+            .map(|_| exploration_factor > rng.gen())
+            .collect();
+        let choice = choose_group_actions(action_mask, action_groups, |group, actions| {
+            self.current_state = TabularState {
+                state: state.clone(),
+                actions: actions.to_vec(),
+                reward: 0.0,
+            };
+            self.pick_action(explore[group], rng)
+        });
+        self.last_invalid_choices = choice.invalid_choices;
+        self.last_actions.clear();
+        for action in choice.actions.iter() {
+            self.take_action(action);
+        }
+        choice.actions
+    }
+
+    // Each action taken in the last tick is credited with the reward of its group
+    pub fn train(
+        &mut self,
+        old_state: &CompanyState,
+        group_rewards: &[f64],
+        new_state: &CompanyState,
+        action_groups: &[Vec<usize>],
+    ) {
+        let old_state = old_state.get_bucketed();
+        let new_bucketed_state = new_state.get_bucketed();
+        let learning_strategy =
+            QLearning::new(self.learning_rate, self.discount, self.initial_value);
+        let mut values: Vec<(usize, f64)> = vec![];
+        for ((action, group), reward) in self
+            .last_actions
+            .iter()
+            .zip(action_groups.iter())
+            .zip(group_rewards.iter())
+        {
+            // Only actions of the same group compete for the next state value
+            let new_state = TabularState {
+                state: new_bucketed_state.clone(),
+                actions: group.clone(),
+                reward: *reward,
+            };
+            let new_action_values: Option<HashMap<usize, f64>> =
+                self.q_values.get(&new_state.state).map(|action_values| {
+                    new_state
+                        .actions()
+                        .into_iter()
+                        .filter_map(|next_action| {
+                            action_values
+                                .get(&next_action)
+                                .map(|value| (next_action, *value))
+                        })
+                        .collect()
+                });
+            let old_value = self
+                .q_values
+                .get(&old_state)
+                .and_then(|action_values| action_values.get(action));
+            let value = <QLearning as LearningStrategy<TabularState>>::value(
+                &learning_strategy,
+                &new_action_values.as_ref(),
+                &old_value,
+                new_state.reward(),
+            );
+            values.push((*action, value));
+        }
+        let action_values = self.q_values.entry(old_state).or_default();
+        for (action, value) in values {
+            action_values.insert(action, value);
+        }
+    }
+}
@@ -1,7 +1,9 @@
 use serde::{Deserialize, Serialize};
 // Constants
+// Number of past ticks averaged in the price history features
+pub const FEATURE_WINDOW: usize = 10;
 
-#[derive(PartialEq, Eq, Hash, Clone, Serialize, Deserialize)]
+#[derive(PartialEq, Eq, Hash, Clone, Default, Serialize, Deserialize)]
 pub struct CompanyState {
     // Stockpile
     pub stock: Vec<usize>,
@@ -11,6 +13,11 @@ pub struct CompanyState {
     // Price and order index
     pub price_index: Vec<usize>,
     pub order_index: Vec<usize>,
+    pub employees: usize,
+    // Price history
+    pub last_price: Vec<usize>,
+    pub vwap: Vec<usize>,
+    pub traded_volume: Vec<usize>,
 }
 
 impl CompanyState {}
@@ -18,10 +25,30 @@ impl CompanyState {}
 impl CompanyState {
     pub fn new(resource_count: usize) -> CompanyState {
         CompanyState {
-            stock: (0..resource_count).map(|_| 0 as usize).collect(),
+            stock: (0..resource_count).map(|_| 0_usize).collect(),
             currency: 0,
-            price_index: (0..resource_count).map(|_| 0 as usize).collect(),
-            order_index: (0..resource_count).map(|_| 0 as usize).collect(),
+            price_index: (0..resource_count).map(|_| 0_usize).collect(),
+            order_index: (0..resource_count).map(|_| 0_usize).collect(),
+            employees: 0,
+            last_price: (0..resource_count).map(|_| 0_usize).collect(),
+            vwap: (0..resource_count).map(|_| 0_usize).collect(),
+            traded_volume: (0..resource_count).map(|_| 0_usize).collect(),
+        }
+    }
+
+    // Logarithmic buckets keep the number of distinct states small for tabular agents
+    pub fn get_bucketed(&self) -> CompanyState {
+        let bucket = |x: &usize| (usize::BITS - x.leading_zeros()) as usize;
+        let buckets = |values: &[usize]| values.iter().map(bucket).collect();
+        CompanyState {
+            stock: buckets(&self.stock),
+            currency: bucket(&self.currency),
+            price_index: buckets(&self.price_index),
+            order_index: buckets(&self.order_index),
+            employees: bucket(&self.employees),
+            last_price: buckets(&self.last_price),
+            vwap: buckets(&self.vwap),
+            traded_volume: buckets(&self.traded_volume),
         }
     }
 
@@ -34,6 +61,14 @@ impl CompanyState {
         return_value.append(&mut price_index_vec);
         let mut order_index_vec: Vec<f64> = self.order_index.iter().map(|x| *x as f64).collect();
         return_value.append(&mut order_index_vec);
+        return_value.push(self.employees as f64);
+        let mut last_price_vec: Vec<f64> = self.last_price.iter().map(|x| *x as f64).collect();
+        return_value.append(&mut last_price_vec);
+        let mut vwap_vec: Vec<f64> = self.vwap.iter().map(|x| *x as f64).collect();
+        return_value.append(&mut vwap_vec);
+        let mut traded_volume_vec: Vec<f64> =
+            self.traded_volume.iter().map(|x| *x as f64).collect();
+        return_value.append(&mut traded_volume_vec);
         return_value
     }
 }
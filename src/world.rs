@@ -1,15 +1,26 @@
+use crate::economy::contract::Position;
+use crate::economy::government::Government;
+use crate::market::book_request::BookRequest;
 use crate::market::marketplace::Marketplace;
-use crate::market::offer::Offer;
+use crate::market::offer::{Offer, UnprocessedOffer};
 use crate::market::order::Order;
-use crate::reinforcement_learning::action::ActionSpace;
+use crate::market::processor_offer::{ProcessorOffer, ProcessorOrder};
+use crate::market::shipment::Shipment;
+use crate::reinforcement_learning::action::{ActionSpace, ActionSpaceDefinition};
 use crate::world_data::company_data::CompanyData;
 use crate::world_data::consumer_data::ConsumerData;
+use crate::world_data::contract_data::ContractData;
+use crate::world_data::labor_data::LaborData;
 use crate::world_data::market_data::MarketData;
 use crate::world_data::processor_data::ProcessorData;
 use crate::world_data::producer_data::ProducerData;
 use crate::world_data::recipe_data::RecipeData;
+use crate::world_data::region_data::{RegionData, RegionHandle};
 use crate::world_data::resource_data::ResourceData;
+use crate::world_data::storage_data::{OverflowBehavior, StorageData};
 use log::info;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -23,6 +34,27 @@ pub struct World {
     pub market_data: MarketData,
     pub market_place: Marketplace,
     pub actionspace: ActionSpace,
+    #[serde(default)]
+    pub storage_data: StorageData,
+    #[serde(default)]
+    pub labor_data: LaborData,
+    #[serde(default)]
+    pub government: Government,
+    // Remote regions, companies in the home region trade on market_data
+    #[serde(default)]
+    pub region_data: RegionData,
+    #[serde(default)]
+    pub contract_data: ContractData,
+    #[serde(default)]
+    pub current_tick: usize,
+    #[serde(skip, default = "World::default_rng")]
+    pub rng: StdRng,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl World {
@@ -36,15 +68,48 @@ impl World {
             consumer_data: ConsumerData::new(),
             market_data: MarketData::new(0),
             market_place: Marketplace::new(),
-            actionspace: ActionSpace::new(0, 0),
+            actionspace: ActionSpace::new(&ActionSpaceDefinition::new(), 0, 0, None),
+            storage_data: StorageData::new(),
+            labor_data: LaborData::new(),
+            government: Government::new(),
+            region_data: RegionData::new(),
+            contract_data: ContractData::new(),
+            current_tick: 0,
+            rng: World::default_rng(),
         }
     }
 
+    fn default_rng() -> StdRng {
+        StdRng::from_entropy()
+    }
+
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     pub fn print_world_info(&self) {
         for company in self.company_data.companies.iter() {
             info!("Company: {}", company.name);
             info!("Currency: {}", company.currency);
             info!("Value: {}", company.company_value);
+            info!("Warehouses: {}", company.warehouses);
+            info!("Employees: {} @ {} wage", company.employees, company.wage);
+            info!("Spoilage: {}", company.statistics.get_total_spoilage());
+            info!("Market fees: {}", company.statistics.market_fees);
+            info!(
+                "Region: {}",
+                self.region_data
+                    .get_region_name_by_handle(company.region)
+                    .unwrap_or_default()
+            );
+            info!(
+                "Transport costs: {}, tariffs paid: {}",
+                company.statistics.transport_costs, company.statistics.tariffs_paid
+            );
+            info!(
+                "Posted margin: {}, contract penalties paid: {}",
+                company.posted_margin, company.statistics.contract_penalties_paid
+            );
             info!("Processors:");
             for processor in company.processors.iter() {
                 info!(" - {}", processor.name);
@@ -56,14 +121,11 @@ impl World {
         info!("Market offers:");
         for offer in self.market_data.offers.iter() {
             let mut company_name: &str = "Producer";
-            match offer.1.company {
-                Some(company_handle) => {
-                    company_name = self
-                        .company_data
-                        .get_company_name_by_handle(company_handle)
-                        .unwrap();
-                }
-                None => {}
+            if let Some(company_handle) = offer.1.company {
+                company_name = self
+                    .company_data
+                    .get_company_name_by_handle(company_handle)
+                    .unwrap();
             }
 
             let resource_name = self
@@ -79,14 +141,11 @@ impl World {
         info!("Market orders:");
         for order in self.market_data.orders.iter() {
             let mut company_name: &str = "Consumer";
-            match order.1.company {
-                Some(company_handle) => {
-                    company_name = self
-                        .company_data
-                        .get_company_name_by_handle(company_handle)
-                        .unwrap();
-                }
-                None => {}
+            if let Some(company_handle) = order.1.company {
+                company_name = self
+                    .company_data
+                    .get_company_name_by_handle(company_handle)
+                    .unwrap();
             }
             let resource_name = self
                 .resource_data
@@ -98,21 +157,100 @@ impl World {
             );
         }
         info!("================================================================================");
+        info!("Processor offers:");
+        for offer in self.market_data.processor_offers.values() {
+            let company_name = self
+                .company_data
+                .get_company_name_by_handle(offer.company)
+                .unwrap();
+            info!(
+                " - Company {} offers {} @ {} credits",
+                company_name, offer.processor.name, offer.price
+            );
+        }
+        info!("================================================================================");
+        info!("Market statistics:");
+        for resource in 0..self.market_data.resource_count {
+            let candle = match self.market_data.get_last_candle(resource) {
+                Some(candle) => candle,
+                None => continue,
+            };
+            let resource_name = self
+                .resource_data
+                .get_resource_name_by_handle(resource)
+                .unwrap();
+            info!(
+                " - {}: last {} credits/unit, vwap {} credits/unit, volume {}, spread {:?}",
+                resource_name,
+                candle.close,
+                candle.get_vwap(),
+                candle.volume,
+                candle.get_spread()
+            );
+        }
+        for (region_handle, region) in self.region_data.regions.iter().enumerate() {
+            let company_count = self
+                .company_data
+                .companies
+                .iter()
+                .filter(|company| company.region == region_handle + 1)
+                .count();
+            info!(
+                "Region {}: {} companies, {} offers, {} orders",
+                region.name,
+                company_count,
+                region.market_data.offers.len(),
+                region.market_data.orders.len()
+            );
+        }
+        info!("Shipments in transit: {}", self.region_data.shipments.len());
+        info!(
+            "Open forward contracts: {}, futures listings: {}",
+            self.contract_data.contracts.len(),
+            self.contract_data.futures.len()
+        );
+        info!(
+            "Supply contracts: {}, supply proposals: {}",
+            self.contract_data.supply_contracts.len(),
+            self.contract_data.supply_proposals.len()
+        );
+        info!("Average wage: {}", self.labor_data.get_average_wage());
+        info!(
+            "Government budget: {}, tariff revenue: {}",
+            self.government.budget, self.government.tariff_revenue
+        );
+        info!("Market mode: {:?}", self.market_place.market_mode);
+        info!(
+            "Market operator account: {}",
+            self.market_place.operator_account
+        );
+        info!(
+            "Processor manufacturer balance: {}",
+            self.processor_data.get_manufacturer_balance()
+        );
+        info!("================================================================================");
     }
 
     fn update_producers(&mut self) {
         for producer in self.producer_data.producers.iter_mut() {
             producer.tick();
+            let (market_data, market_place) = self.region_data.get_market(
+                producer.region,
+                &mut self.market_data,
+                &mut self.market_place,
+            );
             for offer in producer.offers.iter_mut() {
-                self.market_place.place_offer(
+                market_place.place_offer(
                     Offer {
                         resource: offer.resource,
                         amount: offer.amount,
                         price_per_unit: offer.price_per_unit,
                         company: None,
                         time_to_live: offer.time_to_live,
+                        household: None,
+                        order_type: offer.order_type,
                     },
-                    &mut self.market_data,
+                    market_data,
                 );
             }
             producer.offers.clear();
@@ -122,84 +260,454 @@ impl World {
     fn update_consumers(&mut self) {
         for consumer in self.consumer_data.consumers.iter_mut() {
             consumer.tick();
+            let (market_data, market_place) = self.region_data.get_market(
+                consumer.region,
+                &mut self.market_data,
+                &mut self.market_place,
+            );
             for order in consumer.orders.iter_mut() {
-                self.market_place.place_order(
+                market_place.place_order(
                     Order {
                         resource: order.resource,
                         amount: order.amount,
                         max_price_per_unit: order.max_price_per_unit,
                         company: None,
                         time_to_live: order.time_to_live,
+                        order_type: order.order_type,
                     },
-                    &mut self.market_data,
+                    market_data,
                 );
             }
             consumer.orders.clear();
         }
     }
 
+    fn update_households(&mut self) {
+        let labor_resource = match self.labor_data.labor_resource {
+            Some(labor_resource) => labor_resource,
+            None => return,
+        };
+        for (household_handle, household) in self.labor_data.households.iter_mut().enumerate() {
+            household.tick(labor_resource, self.labor_data.hours_per_worker);
+            let (market_data, market_place) = self.region_data.get_market(
+                household.region,
+                &mut self.market_data,
+                &mut self.market_place,
+            );
+            for offer in household.offers.iter_mut() {
+                market_place.place_offer(
+                    Offer {
+                        resource: offer.resource,
+                        amount: offer.amount,
+                        price_per_unit: offer.price_per_unit,
+                        company: None,
+                        time_to_live: offer.time_to_live,
+                        household: Some(household_handle),
+                        order_type: offer.order_type,
+                    },
+                    market_data,
+                );
+            }
+            household.offers.clear();
+        }
+    }
+
     fn update_companies(&mut self, train: bool, exploration_factor: f64) {
         // TODO: Shuffle iterator in order to avoid bias
         for (company_handle, company) in self.company_data.companies.iter_mut().enumerate() {
+            let productions =
+                company.produce(&self.recipe_data, &self.storage_data, &self.resource_data);
+            self.government.pay_subsidies(company, &productions);
             company.tick(
                 &self.recipe_data,
                 &self.market_data,
-                self.processor_data.processor_price,
+                &self.region_data,
+                &mut self.processor_data,
+                &self.storage_data,
                 &self.actionspace,
                 train,
                 exploration_factor,
+                &mut self.rng,
+            );
+            // Employees are paid through a labor order each tick
+            if let Some(labor_resource) = self.labor_data.labor_resource {
+                company.place_labor_order(labor_resource, self.labor_data.hours_per_worker);
+            }
+            let (market_data, market_place) = self.region_data.get_market(
+                company.region,
+                &mut self.market_data,
+                &mut self.market_place,
             );
             // Create offers
-            for offer in company.offers.iter_mut() {
-                if !company
-                    .stock
-                    .remove_from_stock_if_possible(offer.resource, offer.amount)
+            for offer in std::mem::take(&mut company.offers) {
+                let listing_fee = market_place.fees.offer_listing_fee;
+                if company.currency < listing_fee
+                    || !company
+                        .stock
+                        .remove_from_stock_if_possible(offer.resource, offer.amount)
                 {
                     continue;
                 }
-                self.market_place.place_offer(
+                let offer_handle = market_place.place_offer(
                     Offer {
                         resource: offer.resource,
                         amount: offer.amount,
                         price_per_unit: offer.price_per_unit,
                         company: Some(company_handle),
                         time_to_live: offer.time_to_live,
+                        household: None,
+                        order_type: offer.order_type,
                     },
-                    &mut self.market_data,
+                    market_data,
                 );
+                if let Some(offer_handle) = offer_handle {
+                    market_place.charge_fee(company, listing_fee);
+                    company.open_offers.push(offer_handle);
+                }
             }
-            company.offers.clear();
             // Create orders
-            for order in company.orders.iter_mut() {
+            for order in std::mem::take(&mut company.orders) {
                 let order_price = order.max_price_per_unit * order.amount;
-                if company.currency < order_price {
+                let listing_fee = market_place.fees.order_listing_fee;
+                if company.currency < order_price + listing_fee {
                     continue;
                 }
                 company.currency -= order_price;
-                self.market_place.place_order(
+                let order_handle = market_place.place_order(
                     Order {
                         resource: order.resource,
                         amount: order.amount,
                         max_price_per_unit: order.max_price_per_unit,
                         company: Some(company_handle),
                         time_to_live: order.time_to_live,
+                        order_type: order.order_type,
+                    },
+                    market_data,
+                );
+                match order_handle {
+                    Some(order_handle) => {
+                        market_place.charge_fee(company, listing_fee);
+                        company.open_orders.push(order_handle);
+                    }
+                    None => company.currency += order_price,
+                }
+            }
+            // Create processor offers
+            for offer in company.processor_offers.drain(..) {
+                market_place.place_processor_offer(
+                    ProcessorOffer {
+                        processor: offer.processor,
+                        price: offer.price,
+                        company: company_handle,
+                        time_to_live: offer.time_to_live,
+                    },
+                    market_data,
+                );
+            }
+            // Create processor orders
+            for order in company.processor_orders.drain(..) {
+                if company.currency < order.max_price {
+                    continue;
+                }
+                company.currency -= order.max_price;
+                market_place.place_processor_order(
+                    ProcessorOrder {
+                        offer: order.offer,
+                        max_price: order.max_price,
+                        company: company_handle,
                     },
-                    &mut self.market_data,
+                    market_data,
+                );
+            }
+            // Create shipments, transport and tariffs are paid up front
+            for export in std::mem::take(&mut company.exports) {
+                let route = match self
+                    .region_data
+                    .get_route(company.region, export.destination)
+                {
+                    Some(route) => route.clone(),
+                    None => continue,
+                };
+                let transport_cost = route.cost_per_unit * export.offer.amount;
+                let tariff = self.government.get_tariff(
+                    company.region,
+                    export.destination,
+                    export.offer.amount * export.offer.price_per_unit,
+                );
+                if company.currency < transport_cost + tariff
+                    || !company
+                        .stock
+                        .remove_from_stock_if_possible(export.offer.resource, export.offer.amount)
+                {
+                    continue;
+                }
+                company.currency -= transport_cost;
+                company.statistics.transport_costs += transport_cost;
+                self.government.collect_tariff(company, tariff);
+                company.statistics.exported_amount += export.offer.amount;
+                self.region_data.shipments.push(Shipment {
+                    company: company_handle,
+                    destination: export.destination,
+                    offer: export.offer,
+                    remaining_ticks: route.delay,
+                    is_return: false,
+                });
+            }
+            // Create futures listings, the futures book is run by the home market operator
+            for future in std::mem::take(&mut company.futures) {
+                let listing_fee = match future.position {
+                    Position::Long => self.market_place.fees.order_listing_fee,
+                    Position::Short => self.market_place.fees.offer_listing_fee,
+                };
+                if company.currency < listing_fee {
+                    continue;
+                }
+                if self
+                    .contract_data
+                    .place_future(company_handle, future, self.current_tick)
+                    .is_some()
+                {
+                    self.market_place.charge_fee(company, listing_fee);
+                }
+            }
+            // Propose and accept supply contracts
+            for request in std::mem::take(&mut company.supply_requests) {
+                self.contract_data.handle_supply_request(
+                    company_handle,
+                    request,
+                    self.current_tick,
+                );
+            }
+        }
+    }
+
+    fn update_shipments(&mut self) {
+        let shipments = std::mem::take(&mut self.region_data.shipments);
+        for mut shipment in shipments {
+            if shipment.remaining_ticks > 0 {
+                shipment.remaining_ticks -= 1;
+                self.region_data.shipments.push(shipment);
+                continue;
+            }
+            if shipment.is_return {
+                self.company_data.companies[shipment.company]
+                    .stock
+                    .add_to_stock(shipment.offer.resource, shipment.offer.amount);
+                continue;
+            }
+            // Arrived resources are offered in the destination region, unsold resources are
+            // returned to the company when the offer expires
+            let (market_data, market_place) = self.region_data.get_market(
+                shipment.destination,
+                &mut self.market_data,
+                &mut self.market_place,
+            );
+            let offer_handle = market_place.place_offer(
+                Offer {
+                    resource: shipment.offer.resource,
+                    amount: shipment.offer.amount,
+                    price_per_unit: shipment.offer.price_per_unit,
+                    company: Some(shipment.company),
+                    time_to_live: shipment.offer.time_to_live,
+                    household: None,
+                    order_type: shipment.offer.order_type,
+                },
+                market_data,
+            );
+            if offer_handle.is_some() {
+                let listing_fee = market_place.fees.offer_listing_fee;
+                market_place.charge_fee(
+                    &mut self.company_data.companies[shipment.company],
+                    listing_fee,
                 );
             }
-            company.orders.clear();
+        }
+    }
+
+    fn update_company_books(&mut self) {
+        for company_handle in 0..self.company_data.companies.len() {
+            let book_requests =
+                std::mem::take(&mut self.company_data.companies[company_handle].book_requests);
+            let (market_data, market_place) = self.region_data.get_market(
+                self.company_data.companies[company_handle].region,
+                &mut self.market_data,
+                &mut self.market_place,
+            );
+            for book_request in book_requests {
+                match book_request {
+                    BookRequest::CancelOrder(order_handle) => {
+                        market_place.cancel_order(
+                            company_handle,
+                            order_handle,
+                            market_data,
+                            &mut self.company_data.companies,
+                        );
+                    }
+                    BookRequest::CancelOffer(offer_handle) => {
+                        market_place.cancel_offer(
+                            company_handle,
+                            offer_handle,
+                            market_data,
+                            &mut self.company_data.companies,
+                        );
+                    }
+                    BookRequest::AmendOrder(order_handle, amount, max_price_per_unit) => {
+                        market_place.amend_order(
+                            company_handle,
+                            order_handle,
+                            amount,
+                            max_price_per_unit,
+                            market_data,
+                            &mut self.company_data.companies,
+                        );
+                    }
+                    BookRequest::AmendOffer(offer_handle, amount, price_per_unit) => {
+                        market_place.amend_offer(
+                            company_handle,
+                            offer_handle,
+                            amount,
+                            price_per_unit,
+                            market_data,
+                            &mut self.company_data.companies,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn update_stock_spoilage(&mut self) {
+        // Stock spoils after production, so resources delivered by the market, e.g. worker-hours,
+        // can be used for one tick before they spoil
+        for company in self.company_data.companies.iter_mut() {
+            company.apply_spoilage(&self.resource_data, &mut self.rng);
+        }
+    }
+
+    fn update_offer_spoilage(&mut self) {
+        self.market_place.apply_spoilage(
+            &mut self.market_data,
+            &self.resource_data,
+            &mut self.company_data.companies,
+            &mut self.rng,
+        );
+        for region in self.region_data.regions.iter_mut() {
+            region.market_place.apply_spoilage(
+                &mut region.market_data,
+                &self.resource_data,
+                &mut self.company_data.companies,
+                &mut self.rng,
+            );
+        }
+    }
+
+    fn update_markets(&mut self) {
+        self.market_place.tick(
+            &mut self.market_data,
+            &mut self.company_data.companies,
+            &mut self.labor_data.households,
+            &mut self.government,
+        );
+        let returned_offers = std::mem::take(&mut self.market_place.returned_offers);
+        self.return_offers(0, returned_offers);
+        for region_handle in 1..self.region_data.get_region_count() {
+            let region = &mut self.region_data.regions[region_handle - 1];
+            region.market_place.tick(
+                &mut region.market_data,
+                &mut self.company_data.companies,
+                &mut self.labor_data.households,
+                &mut self.government,
+            );
+            let returned_offers = std::mem::take(&mut region.market_place.returned_offers);
+            self.return_offers(region_handle, returned_offers);
+        }
+    }
+
+    // Unsold exports are shipped back to the company's region at its expense
+    fn return_offers(&mut self, region: RegionHandle, offers: Vec<Offer>) {
+        for offer in offers {
+            let company_handle = match offer.company {
+                Some(company_handle) => company_handle,
+                None => continue,
+            };
+            let company = &mut self.company_data.companies[company_handle];
+            let route = match self.region_data.get_route(region, company.region) {
+                Some(route) if company.region != region => route,
+                _ => {
+                    company.stock.add_to_stock(offer.resource, offer.amount);
+                    continue;
+                }
+            };
+            let transport_cost = route.cost_per_unit * offer.amount;
+            company.currency -= transport_cost;
+            company.statistics.transport_costs += transport_cost;
+            self.region_data.shipments.push(Shipment {
+                company: company_handle,
+                destination: company.region,
+                offer: UnprocessedOffer {
+                    resource: offer.resource,
+                    amount: offer.amount,
+                    price_per_unit: offer.price_per_unit,
+                    time_to_live: offer.time_to_live,
+                    order_type: offer.order_type,
+                },
+                remaining_ticks: route.delay,
+                is_return: true,
+            });
+        }
+    }
+
+    fn update_storage(&mut self) {
+        for company in self.company_data.companies.iter_mut() {
+            if self.storage_data.overflow_behavior == OverflowBehavior::ForcedSale {
+                company.liquidate_overflow(
+                    &self.storage_data,
+                    &self.resource_data,
+                    self.region_data
+                        .get_market_data(company.region, &self.market_data),
+                );
+            }
+            company.pay_holding_costs(&self.storage_data, &self.resource_data);
         }
     }
 
     pub fn tick(&mut self, train: bool, exploration_factor: f64) {
+        // Rebuild market books after loading
+        self.market_data.ensure_books();
+        for region in self.region_data.regions.iter_mut() {
+            region.market_data.ensure_books();
+        }
         // Update producers
         self.update_producers();
         // Update consumers
         self.update_consumers();
+        // Update households
+        self.update_households();
         // Update companies
         self.update_companies(train, exploration_factor);
-        // Update market
-        self.market_place
-            .tick(&mut self.market_data, &mut self.company_data.companies);
+        // Cancel and amend open listings
+        self.update_company_books();
+        // Update spoilage of company stock
+        self.update_stock_spoilage();
+        // Deliver shipments to other regions
+        self.update_shipments();
+        // Update markets of all regions
+        self.update_markets();
+        // Match futures, settle matured contracts and execute supply contracts
+        self.contract_data.tick(
+            self.current_tick,
+            &mut self.company_data.companies,
+            &mut self.market_data,
+            &mut self.market_place,
+            &mut self.government,
+        );
+        // Update spoilage of open offers
+        self.update_offer_spoilage();
+        // Update storage
+        self.update_storage();
+        // Collect taxes
+        self.government
+            .tick(&mut self.company_data.companies, &self.processor_data);
+        self.current_tick += 1;
     }
 }
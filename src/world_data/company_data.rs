@@ -6,6 +6,12 @@ pub struct CompanyData {
     pub companies: Vec<Company>,
 }
 
+impl Default for CompanyData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl CompanyData {
     pub fn new() -> CompanyData {
         CompanyData {
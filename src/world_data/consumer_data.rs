@@ -6,6 +6,12 @@ pub struct ConsumerData {
     pub consumers: Vec<Consumer>,
 }
 
+impl Default for ConsumerData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ConsumerData {
     pub fn new() -> ConsumerData {
         ConsumerData { consumers: vec![] }
@@ -0,0 +1,520 @@
+use crate::economy::company::{Company, CompanyHandle};
+use crate::economy::contract::{
+    ContractHandle, ForwardContract, FutureListing, Position, SupplyContract,
+    SupplyContractRequest, SupplyProposal, UnprocessedFuture,
+};
+use crate::economy::government::Government;
+use crate::economy::resource::ResourceHandle;
+use crate::market::marketplace::Marketplace;
+use crate::market::trade::Trade;
+use crate::world_data::market_data::MarketData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub type FutureListingHandle = usize;
+pub type SupplyProposalHandle = usize;
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ContractData {
+    pub contracts: HashMap<ContractHandle, ForwardContract>,
+    pub futures: HashMap<FutureListingHandle, FutureListing>,
+    pub supply_contracts: HashMap<ContractHandle, SupplyContract>,
+    pub supply_proposals: HashMap<SupplyProposalHandle, SupplyProposal>,
+    next_contract_id: ContractHandle,
+    next_future_id: FutureListingHandle,
+    next_proposal_id: SupplyProposalHandle,
+    // Futures are delivered on multiples of this period
+    pub futures_period: usize,
+    // Share of the contract value both parties post as margin
+    pub margin_rate: f64,
+    // Share of the contract price paid to the counterparty per undelivered or unpaid unit
+    pub penalty_rate: f64,
+    // Supply contracts are terminated after this many breached deliveries
+    pub max_breaches: usize,
+}
+
+impl Default for ContractData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContractData {
+    pub fn new() -> ContractData {
+        ContractData {
+            contracts: HashMap::new(),
+            futures: HashMap::new(),
+            supply_contracts: HashMap::new(),
+            supply_proposals: HashMap::new(),
+            next_contract_id: 0,
+            next_future_id: 0,
+            next_proposal_id: 0,
+            futures_period: 50,
+            margin_rate: 0.1,
+            penalty_rate: 0.5,
+            max_breaches: 3,
+        }
+    }
+
+    pub fn get_delivery_tick(&self, current_tick: usize, maturity: usize) -> usize {
+        let period = self.futures_period.max(1);
+        (current_tick / period + maturity.max(1)) * period
+    }
+
+    pub fn get_margin(&self, amount: f64, price_per_unit: f64) -> f64 {
+        self.margin_rate * amount * price_per_unit
+    }
+
+    // Agrees on a forward contract if both parties can post their margin
+    #[allow(clippy::too_many_arguments)]
+    pub fn create_forward(
+        &mut self,
+        buyer: CompanyHandle,
+        seller: CompanyHandle,
+        resource: ResourceHandle,
+        amount: f64,
+        price_per_unit: f64,
+        delivery_tick: usize,
+        companies: &mut [Company],
+    ) -> Option<ContractHandle> {
+        let margin = self.get_margin(amount, price_per_unit);
+        if buyer == seller
+            || amount <= 0.0
+            || companies[buyer].currency < margin
+            || companies[seller].currency < margin
+        {
+            return None;
+        }
+        companies[buyer].currency -= margin;
+        companies[buyer].posted_margin += margin;
+        companies[seller].currency -= margin;
+        companies[seller].posted_margin += margin;
+        self.next_contract_id += 1;
+        self.contracts.insert(
+            self.next_contract_id,
+            ForwardContract {
+                buyer,
+                seller,
+                resource,
+                amount,
+                price_per_unit,
+                delivery_tick,
+                buyer_margin: margin,
+                seller_margin: margin,
+            },
+        );
+        Some(self.next_contract_id)
+    }
+
+    pub fn place_future(
+        &mut self,
+        company: CompanyHandle,
+        future: UnprocessedFuture,
+        current_tick: usize,
+    ) -> Option<FutureListingHandle> {
+        if future.amount <= 0.0 || future.price_per_unit <= 0.0 {
+            return None;
+        }
+        self.next_future_id += 1;
+        self.futures.insert(
+            self.next_future_id,
+            FutureListing {
+                company,
+                position: future.position,
+                resource: future.resource,
+                amount: future.amount,
+                price_per_unit: future.price_per_unit,
+                delivery_tick: self.get_delivery_tick(current_tick, future.maturity),
+                time_to_live: 100,
+            },
+        );
+        Some(self.next_future_id)
+    }
+
+    // Net amount a company has to receive (positive) or deliver (negative) of a resource
+    pub fn get_open_position(&self, company: CompanyHandle, resource: ResourceHandle) -> f64 {
+        self.contracts
+            .values()
+            .filter(|contract| contract.resource == resource)
+            .map(|contract| {
+                if contract.buyer == company {
+                    contract.amount
+                } else if contract.seller == company {
+                    -contract.amount
+                } else {
+                    0.0
+                }
+            })
+            .sum()
+    }
+
+    // Matches long and short listings of the same resource and delivery tick, trades happen at
+    // the price of the listing placed first. Both parties pay the market commission.
+    fn match_futures(&mut self, companies: &mut [Company], market_place: &mut Marketplace) {
+        let mut longs: Vec<FutureListingHandle> = vec![];
+        let mut shorts: Vec<FutureListingHandle> = vec![];
+        for (listing_handle, listing) in self.futures.iter() {
+            match listing.position {
+                Position::Long => longs.push(*listing_handle),
+                Position::Short => shorts.push(*listing_handle),
+            }
+        }
+        longs.sort_by(|a, b| {
+            self.futures[b]
+                .price_per_unit
+                .total_cmp(&self.futures[a].price_per_unit)
+                .then(a.cmp(b))
+        });
+        shorts.sort_by(|a, b| {
+            self.futures[a]
+                .price_per_unit
+                .total_cmp(&self.futures[b].price_per_unit)
+                .then(a.cmp(b))
+        });
+        for long_handle in longs.iter() {
+            for short_handle in shorts.iter() {
+                let long = &self.futures[long_handle];
+                let short = &self.futures[short_handle];
+                if long.amount <= 0.0 {
+                    break;
+                }
+                if short.amount <= 0.0
+                    || short.resource != long.resource
+                    || short.delivery_tick != long.delivery_tick
+                    || short.company == long.company
+                    || short.price_per_unit > long.price_per_unit
+                {
+                    continue;
+                }
+                let amount = long.amount.min(short.amount);
+                let price_per_unit = match long_handle < short_handle {
+                    true => long.price_per_unit,
+                    false => short.price_per_unit,
+                };
+                let margin = self.get_margin(amount, price_per_unit);
+                // Listings without enough currency for the margin are dropped
+                if companies[long.company].currency < margin {
+                    self.futures.get_mut(long_handle).unwrap().amount = 0.0;
+                    break;
+                }
+                if companies[short.company].currency < margin {
+                    self.futures.get_mut(short_handle).unwrap().amount = 0.0;
+                    continue;
+                }
+                let (buyer, seller, resource, delivery_tick) = (
+                    long.company,
+                    short.company,
+                    long.resource,
+                    long.delivery_tick,
+                );
+                if self
+                    .create_forward(
+                        buyer,
+                        seller,
+                        resource,
+                        amount,
+                        price_per_unit,
+                        delivery_tick,
+                        companies,
+                    )
+                    .is_some()
+                {
+                    let commission = market_place.fees.get_commission(amount * price_per_unit);
+                    market_place.charge_fee(&mut companies[buyer], commission);
+                    market_place.charge_fee(&mut companies[seller], commission);
+                }
+                self.futures.get_mut(long_handle).unwrap().amount -= amount;
+                self.futures.get_mut(short_handle).unwrap().amount -= amount;
+            }
+        }
+    }
+
+    fn cleanup_dead_futures(&mut self, current_tick: usize) {
+        self.futures.retain(|_, listing| {
+            listing.time_to_live = listing.time_to_live.saturating_sub(1);
+            listing.amount > 0.0 && listing.time_to_live > 0 && listing.delivery_tick > current_tick
+        });
+    }
+
+    // Delivers as far as the seller has stock and the buyer has currency, the party responsible
+    // for any shortfall pays a penalty to its counterparty. Returns the amount the seller could
+    // deliver and the amount the buyer paid for.
+    fn deliver(
+        &self,
+        seller: CompanyHandle,
+        buyer: CompanyHandle,
+        resource: ResourceHandle,
+        amount: f64,
+        price_per_unit: f64,
+        companies: &mut [Company],
+    ) -> (f64, f64) {
+        let available = companies[seller]
+            .stock
+            .resources
+            .get(&resource)
+            .copied()
+            .unwrap_or(0.0)
+            .max(0.0);
+        let deliverable = available.min(amount);
+        let affordable = match price_per_unit > 0.0 {
+            true => (companies[buyer].currency / price_per_unit).max(0.0),
+            false => deliverable,
+        };
+        let delivered = deliverable.min(affordable);
+        companies[seller]
+            .stock
+            .remove_from_stock_if_possible(resource, delivered);
+        companies[buyer].stock.add_to_stock(resource, delivered);
+        companies[buyer].currency -= delivered * price_per_unit;
+        companies[seller].currency += delivered * price_per_unit;
+        // Penalties
+        let seller_penalty = self.penalty_rate * price_per_unit * (amount - deliverable);
+        companies[seller].currency -= seller_penalty;
+        companies[seller].statistics.contract_penalties_paid += seller_penalty;
+        companies[buyer].currency += seller_penalty;
+        companies[buyer].statistics.contract_penalties_received += seller_penalty;
+        let buyer_penalty = self.penalty_rate * price_per_unit * (deliverable - delivered);
+        companies[buyer].currency -= buyer_penalty;
+        companies[buyer].statistics.contract_penalties_paid += buyer_penalty;
+        companies[seller].currency += buyer_penalty;
+        companies[seller].statistics.contract_penalties_received += buyer_penalty;
+        (deliverable, delivered)
+    }
+
+    // Settles matured forward contracts
+    fn settle_contracts(
+        &mut self,
+        current_tick: usize,
+        companies: &mut [Company],
+        market_data: &mut MarketData,
+        government: &mut Government,
+    ) {
+        let mut matured_contracts: Vec<ContractHandle> = vec![];
+        for (contract_handle, contract) in self.contracts.iter() {
+            if contract.delivery_tick <= current_tick {
+                matured_contracts.push(*contract_handle);
+            }
+        }
+        matured_contracts.sort();
+        for contract_handle in matured_contracts {
+            let contract = self.contracts.remove(&contract_handle).unwrap();
+            let (buyer, seller) = (contract.buyer, contract.seller);
+            // Return margins
+            companies[buyer].currency += contract.buyer_margin;
+            companies[buyer].posted_margin -= contract.buyer_margin;
+            companies[seller].currency += contract.seller_margin;
+            companies[seller].posted_margin -= contract.seller_margin;
+            let (_, delivered) = self.deliver(
+                seller,
+                buyer,
+                contract.resource,
+                contract.amount,
+                contract.price_per_unit,
+                companies,
+            );
+            record_delivery(
+                Trade {
+                    resource: contract.resource,
+                    amount: delivered,
+                    price_per_unit: contract.price_per_unit,
+                    buyer: Some(buyer),
+                    seller: Some(seller),
+                    seller_household: None,
+                },
+                current_tick,
+                companies,
+                market_data,
+                government,
+            );
+            companies[buyer].statistics.settled_contracts += 1;
+            companies[seller].statistics.settled_contracts += 1;
+        }
+    }
+
+    pub fn handle_supply_request(
+        &mut self,
+        company: CompanyHandle,
+        request: SupplyContractRequest,
+        current_tick: usize,
+    ) -> Option<ContractHandle> {
+        match request {
+            SupplyContractRequest::Propose {
+                position,
+                resource,
+                amount,
+                price_per_unit,
+                interval,
+                periods,
+            } => {
+                if amount <= 0.0 || interval == 0 || periods == 0 {
+                    return None;
+                }
+                self.next_proposal_id += 1;
+                self.supply_proposals.insert(
+                    self.next_proposal_id,
+                    SupplyProposal {
+                        proposer: company,
+                        position,
+                        resource,
+                        amount,
+                        price_per_unit,
+                        interval,
+                        periods,
+                        time_to_live: 100,
+                    },
+                );
+                None
+            }
+            SupplyContractRequest::Accept { position, resource } => {
+                self.accept_supply_proposal(company, position, resource, current_tick)
+            }
+        }
+    }
+
+    // Suppliers accept the highest paying customer, customers the cheapest supplier
+    fn accept_supply_proposal(
+        &mut self,
+        company: CompanyHandle,
+        position: Position,
+        resource: ResourceHandle,
+        current_tick: usize,
+    ) -> Option<ContractHandle> {
+        let proposal_handle = self
+            .supply_proposals
+            .iter()
+            .filter(|(_, proposal)| {
+                proposal.resource == resource
+                    && proposal.position != position
+                    && proposal.proposer != company
+            })
+            .min_by(|(a_handle, a), (b_handle, b)| {
+                let by_price = match position {
+                    Position::Short => b.price_per_unit.total_cmp(&a.price_per_unit),
+                    Position::Long => a.price_per_unit.total_cmp(&b.price_per_unit),
+                };
+                by_price.then(a_handle.cmp(b_handle))
+            })
+            .map(|(proposal_handle, _)| *proposal_handle)?;
+        let proposal = self.supply_proposals.remove(&proposal_handle).unwrap();
+        let (supplier, customer) = match position {
+            Position::Short => (company, proposal.proposer),
+            Position::Long => (proposal.proposer, company),
+        };
+        self.next_contract_id += 1;
+        self.supply_contracts.insert(
+            self.next_contract_id,
+            SupplyContract {
+                supplier,
+                customer,
+                resource,
+                amount: proposal.amount,
+                price_per_unit: proposal.price_per_unit,
+                interval: proposal.interval,
+                remaining_deliveries: proposal.periods,
+                next_delivery_tick: current_tick + proposal.interval,
+                breaches: 0,
+            },
+        );
+        Some(self.next_contract_id)
+    }
+
+    fn cleanup_dead_supply_proposals(&mut self) {
+        self.supply_proposals.retain(|_, proposal| {
+            proposal.time_to_live = proposal.time_to_live.saturating_sub(1);
+            proposal.time_to_live > 0
+        });
+    }
+
+    // Executes due deliveries, contracts end after their last delivery or too many breaches
+    fn execute_supply_contracts(
+        &mut self,
+        current_tick: usize,
+        companies: &mut [Company],
+        market_data: &mut MarketData,
+        government: &mut Government,
+    ) {
+        let mut due_contracts: Vec<ContractHandle> = self
+            .supply_contracts
+            .iter()
+            .filter(|(_, contract)| contract.next_delivery_tick <= current_tick)
+            .map(|(contract_handle, _)| *contract_handle)
+            .collect();
+        due_contracts.sort();
+        for contract_handle in due_contracts {
+            let contract = self.supply_contracts[&contract_handle].clone();
+            let (deliverable, delivered) = self.deliver(
+                contract.supplier,
+                contract.customer,
+                contract.resource,
+                contract.amount,
+                contract.price_per_unit,
+                companies,
+            );
+            record_delivery(
+                Trade {
+                    resource: contract.resource,
+                    amount: delivered,
+                    price_per_unit: contract.price_per_unit,
+                    buyer: Some(contract.customer),
+                    seller: Some(contract.supplier),
+                    seller_household: None,
+                },
+                current_tick,
+                companies,
+                market_data,
+                government,
+            );
+            companies[contract.supplier].statistics.supplied_amount += delivered;
+            let contract = self.supply_contracts.get_mut(&contract_handle).unwrap();
+            // Breaches count for the party which failed to deliver or to pay
+            if deliverable < contract.amount {
+                companies[contract.supplier].statistics.supply_breaches += 1;
+            }
+            if delivered < deliverable {
+                companies[contract.customer].statistics.supply_breaches += 1;
+            }
+            if delivered < contract.amount {
+                contract.breaches += 1;
+            }
+            contract.remaining_deliveries -= 1;
+            contract.next_delivery_tick += contract.interval;
+            if contract.remaining_deliveries == 0 || contract.breaches > self.max_breaches {
+                self.supply_contracts.remove(&contract_handle);
+            }
+        }
+    }
+
+    // Contracts are traded on the home market, which collects the fees and logs the deliveries
+    pub fn tick(
+        &mut self,
+        current_tick: usize,
+        companies: &mut [Company],
+        market_data: &mut MarketData,
+        market_place: &mut Marketplace,
+        government: &mut Government,
+    ) {
+        self.match_futures(companies, market_place);
+        self.cleanup_dead_futures(current_tick);
+        self.settle_contracts(current_tick, companies, market_data, government);
+        self.execute_supply_contracts(current_tick, companies, market_data, government);
+        self.cleanup_dead_supply_proposals();
+    }
+}
+
+// Sellers pay VAT on delivered contracts like on spot trades
+fn record_delivery(
+    trade: Trade,
+    current_tick: usize,
+    companies: &mut [Company],
+    market_data: &mut MarketData,
+    government: &mut Government,
+) {
+    if trade.amount <= 0.0 {
+        return;
+    }
+    if let Some(seller) = trade.seller {
+        government.collect_vat(&mut companies[seller], trade.get_volume());
+    }
+    market_data.log.record_trade(current_tick, trade);
+}
@@ -0,0 +1,46 @@
+use crate::economy::household::{Household, HouseholdHandle};
+use crate::economy::resource::ResourceHandle;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+pub struct LaborData {
+    // Resource representing worker-hours, the labor market is disabled without it
+    pub labor_resource: Option<ResourceHandle>,
+    pub hours_per_worker: f64,
+    pub households: Vec<Household>,
+}
+
+impl Default for LaborData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LaborData {
+    pub fn new() -> LaborData {
+        LaborData {
+            labor_resource: None,
+            hours_per_worker: 1.0,
+            households: vec![],
+        }
+    }
+
+    pub fn get_household_by_handle(
+        &mut self,
+        household_handle: HouseholdHandle,
+    ) -> Option<&mut Household> {
+        self.households.get_mut(household_handle)
+    }
+
+    pub fn get_average_wage(&self) -> f64 {
+        let workers: f64 = self.households.iter().map(|x| x.workers).sum();
+        if workers <= 0.0 {
+            return 0.0;
+        }
+        self.households
+            .iter()
+            .map(|x| x.workers * x.reservation_wage)
+            .sum::<f64>()
+            / workers
+    }
+}
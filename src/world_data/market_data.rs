@@ -1,36 +1,384 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
+use crate::economy::company::CompanyHandle;
+use crate::economy::recipe::RecipeHandle;
 use crate::economy::resource::ResourceHandle;
+use crate::market::book_index::BookIndex;
+use crate::market::candle::Candle;
+use crate::market::market_log::{ListingEvent, ListingEventKind, ListingSide, MarketLog};
 use crate::market::offer::Offer;
 use crate::market::order::Order;
+use crate::market::order_type::OrderType;
+use crate::market::price_reference::PriceReference;
+use crate::market::processor_offer::{ProcessorOffer, ProcessorOfferHandle, ProcessorOrder};
+use crate::market::trade::Trade;
 use serde::{Deserialize, Serialize};
 
 pub type OfferHandle = usize;
 pub type OrderHandle = usize;
 
+// Ordered maps keep iteration, and with it float sums, the same every run
 #[derive(Serialize, Deserialize)]
 pub struct MarketData {
-    pub offers: HashMap<OfferHandle, Offer>,
-    pub orders: HashMap<OrderHandle, Order>,
-    pub price_index: HashMap<ResourceHandle, Option<(OfferHandle, f64)>>,
-    pub order_index: HashMap<ResourceHandle, Option<(OrderHandle, f64)>>,
+    pub offers: BTreeMap<OfferHandle, Offer>,
+    pub orders: BTreeMap<OrderHandle, Order>,
+    pub price_index: BTreeMap<ResourceHandle, Option<(OfferHandle, f64)>>,
+    pub order_index: BTreeMap<ResourceHandle, Option<(OrderHandle, f64)>>,
     pub resource_count: usize,
+    #[serde(default)]
+    pub processor_offers: BTreeMap<ProcessorOfferHandle, ProcessorOffer>,
+    #[serde(default)]
+    pub processor_orders: Vec<ProcessorOrder>,
+    // Trades of the current tick, turned into candles at the end of the tick
+    #[serde(default)]
+    pub trades: Vec<Trade>,
+    #[serde(default)]
+    pub history: BTreeMap<ResourceHandle, Vec<Candle>>,
+    // Number of candles kept per resource
+    #[serde(default = "MarketData::default_history_length")]
+    pub history_length: usize,
+    // Sorted views of offers and orders, rebuilt after loading
+    #[serde(skip, default = "BookIndex::asks")]
+    pub offer_book: BookIndex,
+    #[serde(skip, default = "BookIndex::bids")]
+    pub order_book: BookIndex,
+    #[serde(skip)]
+    books_ready: bool,
+    // Number of market ticks so far
+    #[serde(default)]
+    pub current_tick: usize,
+    // Listing and trade history for analysis, only the newest entries are persisted
+    #[serde(default)]
+    pub log: MarketLog,
 }
 
 impl MarketData {
     pub fn new(resource_count: usize) -> MarketData {
-        let mut price_index: HashMap<ResourceHandle, Option<(OfferHandle, f64)>> = HashMap::new();
-        let mut order_index: HashMap<ResourceHandle, Option<(OrderHandle, f64)>> = HashMap::new();
+        let mut price_index: BTreeMap<ResourceHandle, Option<(OfferHandle, f64)>> = BTreeMap::new();
+        let mut order_index: BTreeMap<ResourceHandle, Option<(OrderHandle, f64)>> = BTreeMap::new();
         for resource in 0..resource_count {
             price_index.insert(resource, None);
             order_index.insert(resource, None);
         }
         MarketData {
-            offers: HashMap::new(),
-            orders: HashMap::new(),
-            price_index: price_index,
-            order_index: order_index,
+            offers: BTreeMap::new(),
+            orders: BTreeMap::new(),
+            price_index,
+            order_index,
             resource_count: 0,
+            processor_offers: BTreeMap::new(),
+            processor_orders: vec![],
+            trades: vec![],
+            history: BTreeMap::new(),
+            history_length: MarketData::default_history_length(),
+            offer_book: BookIndex::asks(),
+            order_book: BookIndex::bids(),
+            books_ready: true,
+            current_tick: 0,
+            log: MarketLog::new(),
         }
     }
+
+    // Market offers accept any price and are sorted in front of all other offers
+    fn get_offer_key(offer: &Offer) -> f64 {
+        offer.get_matching_price()
+    }
+
+    // Market orders pay any price and are sorted in front of all other orders
+    fn get_order_key(order: &Order) -> f64 {
+        match order.order_type {
+            OrderType::Market => f64::INFINITY,
+            _ => order.max_price_per_unit,
+        }
+    }
+
+    // Books are not persisted and have to be rebuilt once after loading a world
+    pub fn ensure_books(&mut self) {
+        if self.books_ready {
+            return;
+        }
+        self.offer_book.clear();
+        for (offer_handle, offer) in self.offers.iter() {
+            self.offer_book.insert(
+                *offer_handle,
+                offer.resource,
+                MarketData::get_offer_key(offer),
+            );
+        }
+        self.order_book.clear();
+        for (order_handle, order) in self.orders.iter() {
+            self.order_book.insert(
+                *order_handle,
+                order.resource,
+                MarketData::get_order_key(order),
+            );
+        }
+        for resource in 0..self.resource_count {
+            self.update_best_offer(resource);
+            self.update_best_order(resource);
+        }
+        self.books_ready = true;
+    }
+
+    // Market offers have no limit price and are not part of the price index
+    pub fn update_best_offer(&mut self, resource: ResourceHandle) {
+        let best_offer = self
+            .offer_book
+            .iter(resource)
+            .find(|(offer_handle, _)| self.offers[offer_handle].order_type != OrderType::Market);
+        self.price_index.insert(resource, best_offer);
+    }
+
+    // Market orders have no limit price and are not part of the order index
+    pub fn update_best_order(&mut self, resource: ResourceHandle) {
+        let best_order = self
+            .order_book
+            .iter(resource)
+            .find(|(order_handle, _)| self.orders[order_handle].order_type != OrderType::Market);
+        self.order_index.insert(resource, best_order);
+    }
+
+    pub fn log_offer_event(
+        &mut self,
+        kind: ListingEventKind,
+        offer_handle: OfferHandle,
+        offer: &Offer,
+    ) {
+        self.log.record_listing_event(ListingEvent {
+            tick: self.current_tick,
+            side: ListingSide::Offer,
+            kind,
+            handle: offer_handle,
+            company: offer.company,
+            resource: offer.resource,
+            amount: offer.amount,
+            price_per_unit: offer.price_per_unit,
+        });
+    }
+
+    pub fn log_order_event(
+        &mut self,
+        kind: ListingEventKind,
+        order_handle: OrderHandle,
+        order: &Order,
+    ) {
+        self.log.record_listing_event(ListingEvent {
+            tick: self.current_tick,
+            side: ListingSide::Order,
+            kind,
+            handle: order_handle,
+            company: order.company,
+            resource: order.resource,
+            amount: order.amount,
+            price_per_unit: order.max_price_per_unit,
+        });
+    }
+
+    pub fn insert_offer(&mut self, offer_handle: OfferHandle, offer: Offer) {
+        self.log_offer_event(ListingEventKind::Placed, offer_handle, &offer);
+        let resource = offer.resource;
+        self.offer_book
+            .insert(offer_handle, resource, MarketData::get_offer_key(&offer));
+        self.offers.insert(offer_handle, offer);
+        self.update_best_offer(resource);
+    }
+
+    pub fn insert_order(&mut self, order_handle: OrderHandle, order: Order) {
+        self.log_order_event(ListingEventKind::Placed, order_handle, &order);
+        let resource = order.resource;
+        self.order_book
+            .insert(order_handle, resource, MarketData::get_order_key(&order));
+        self.orders.insert(order_handle, order);
+        self.update_best_order(resource);
+    }
+
+    pub fn remove_offer(&mut self, offer_handle: OfferHandle) -> Option<Offer> {
+        let offer = self.offers.remove(&offer_handle)?;
+        self.offer_book.remove(offer_handle);
+        self.update_best_offer(offer.resource);
+        Some(offer)
+    }
+
+    pub fn remove_order(&mut self, order_handle: OrderHandle) -> Option<Order> {
+        let order = self.orders.remove(&order_handle)?;
+        self.order_book.remove(order_handle);
+        self.update_best_order(order.resource);
+        Some(order)
+    }
+
+    pub fn is_offer_owner(&self, offer_handle: OfferHandle, company: CompanyHandle) -> bool {
+        self.offers
+            .get(&offer_handle)
+            .is_some_and(|offer| offer.company == Some(company))
+    }
+
+    pub fn is_order_owner(&self, order_handle: OrderHandle, company: CompanyHandle) -> bool {
+        self.orders
+            .get(&order_handle)
+            .is_some_and(|order| order.company == Some(company))
+    }
+
+    pub fn reprice_offer(&mut self, offer_handle: OfferHandle, price_per_unit: f64) {
+        let offer = match self.offers.get_mut(&offer_handle) {
+            Some(offer) => offer,
+            None => return,
+        };
+        offer.price_per_unit = price_per_unit;
+        let resource = offer.resource;
+        let key = MarketData::get_offer_key(offer);
+        self.offer_book.insert(offer_handle, resource, key);
+        self.update_best_offer(resource);
+    }
+
+    pub fn reprice_order(&mut self, order_handle: OrderHandle, max_price_per_unit: f64) {
+        let order = match self.orders.get_mut(&order_handle) {
+            Some(order) => order,
+            None => return,
+        };
+        order.max_price_per_unit = max_price_per_unit;
+        let resource = order.resource;
+        let key = MarketData::get_order_key(order);
+        self.order_book.insert(order_handle, resource, key);
+        self.update_best_order(resource);
+    }
+
+    fn default_history_length() -> usize {
+        100
+    }
+
+    // Turns the trades of the current tick into one candle per resource
+    pub fn record_candles(&mut self) {
+        for resource in 0..self.resource_count {
+            let trades: Vec<&Trade> = self
+                .trades
+                .iter()
+                .filter(|trade| trade.resource == resource)
+                .collect();
+            let previous_close = self
+                .get_last_candle(resource)
+                .map_or(0.0, |candle| candle.close);
+            let mut candle = Candle::new(previous_close, &trades);
+            candle.best_bid = self
+                .order_index
+                .get(&resource)
+                .copied()
+                .flatten()
+                .map(|x| x.1);
+            candle.best_ask = self
+                .price_index
+                .get(&resource)
+                .copied()
+                .flatten()
+                .map(|x| x.1);
+            candle.bid_depth = self
+                .order_book
+                .iter(resource)
+                .fold(0.0, |depth, (order_handle, _)| {
+                    depth + self.orders[&order_handle].amount
+                });
+            candle.ask_depth = self
+                .offer_book
+                .iter(resource)
+                .fold(0.0, |depth, (offer_handle, _)| {
+                    depth + self.offers[&offer_handle].amount
+                });
+            let candles = self.history.entry(resource).or_default();
+            candles.push(candle);
+            if candles.len() > self.history_length {
+                let excess = candles.len() - self.history_length;
+                candles.drain(..excess);
+            }
+        }
+        for trade in self.trades.drain(..) {
+            self.log.record_trade(self.current_tick, trade);
+        }
+        self.current_tick += 1;
+    }
+
+    // Oldest candle first
+    pub fn get_candles(&self, resource: ResourceHandle) -> &[Candle] {
+        match self.history.get(&resource) {
+            Some(candles) => candles,
+            None => &[],
+        }
+    }
+
+    pub fn get_last_candle(&self, resource: ResourceHandle) -> Option<&Candle> {
+        self.get_candles(resource).last()
+    }
+
+    // Price of the last trade within the recorded history
+    pub fn get_last_price(&self, resource: ResourceHandle) -> Option<f64> {
+        self.get_candles(resource)
+            .iter()
+            .rev()
+            .find(|candle| candle.trade_count > 0)
+            .map(|candle| candle.close)
+    }
+
+    // Volume weighted average price over the last ticks
+    pub fn get_vwap(&self, resource: ResourceHandle, ticks: usize) -> Option<f64> {
+        let candles = self.get_candles(resource);
+        let recent = &candles[candles.len().saturating_sub(ticks)..];
+        let volume: f64 = recent.iter().map(|candle| candle.volume).sum();
+        let turnover: f64 = recent.iter().map(|candle| candle.turnover).sum();
+        if volume > 0.0 {
+            Some(turnover / volume)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_traded_volume(&self, resource: ResourceHandle, ticks: usize) -> f64 {
+        let candles = self.get_candles(resource);
+        candles[candles.len().saturating_sub(ticks)..]
+            .iter()
+            .map(|candle| candle.volume)
+            .sum()
+    }
+
+    // Best bid if there is any, best ask otherwise
+    pub fn get_market_price(&self, resource: ResourceHandle) -> f64 {
+        if let Some(Some((_, price))) = self.order_index.get(&resource) {
+            return *price;
+        }
+        if let Some(Some((_, price))) = self.price_index.get(&resource) {
+            return *price;
+        }
+        0.0
+    }
+
+    // Reference price for relative listings, falls back to the last traded price
+    pub fn get_reference_price(
+        &self,
+        resource: ResourceHandle,
+        reference: PriceReference,
+    ) -> Option<f64> {
+        let price = match reference {
+            PriceReference::BestBid => self.order_index.get(&resource).cloned().flatten(),
+            PriceReference::BestAsk => self.price_index.get(&resource).cloned().flatten(),
+            PriceReference::LastPrice => None,
+        };
+        price
+            .map(|(_, price)| price)
+            .or_else(|| self.get_last_price(resource))
+    }
+
+    pub fn get_cheapest_processor_offer(
+        &self,
+        recipe: RecipeHandle,
+        excluded_company: Option<CompanyHandle>,
+    ) -> Option<(ProcessorOfferHandle, f64)> {
+        let mut cheapest_offer: Option<(ProcessorOfferHandle, f64)> = None;
+        for (offer_handle, offer) in self.processor_offers.iter() {
+            if offer.recipe() != recipe || Some(offer.company) == excluded_company {
+                continue;
+            }
+            match cheapest_offer {
+                Some((_, price)) if price <= offer.price => {}
+                _ => cheapest_offer = Some((*offer_handle, offer.price)),
+            }
+        }
+        cheapest_offer
+    }
 }
@@ -1,7 +1,11 @@
 pub mod company_data;
 pub mod consumer_data;
+pub mod contract_data;
+pub mod labor_data;
 pub mod market_data;
 pub mod processor_data;
 pub mod producer_data;
 pub mod recipe_data;
+pub mod region_data;
 pub mod resource_data;
+pub mod storage_data;
@@ -1,14 +1,70 @@
+use crate::economy::recipe::RecipeHandle;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize)]
 pub struct ProcessorData {
+    // Default price of a new processor, used for recipes without an explicit price
     pub processor_price: f64,
+    #[serde(default)]
+    pub recipe_prices: HashMap<RecipeHandle, f64>,
+    // Fraction of the new price the manufacturer pays back when a processor is scrapped
+    #[serde(default = "ProcessorData::default_scrap_value_factor")]
+    pub scrap_value_factor: f64,
+    // The processor manufacturer is the sink of new processor purchases and the source of
+    // scrap refunds
+    #[serde(default)]
+    pub manufacturer_revenue: f64,
+    #[serde(default)]
+    pub manufacturer_payouts: f64,
+}
+
+impl Default for ProcessorData {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl ProcessorData {
     pub fn new() -> ProcessorData {
         ProcessorData {
             processor_price: 1000.0,
+            recipe_prices: HashMap::new(),
+            scrap_value_factor: ProcessorData::default_scrap_value_factor(),
+            manufacturer_revenue: 0.0,
+            manufacturer_payouts: 0.0,
+        }
+    }
+
+    // Scrapping returns the full price, as selling a processor did before the manufacturer existed
+    pub fn default_scrap_value_factor() -> f64 {
+        1.0
+    }
+
+    pub fn get_processor_price(&self, recipe: RecipeHandle) -> f64 {
+        match self.recipe_prices.get(&recipe) {
+            Some(price) => *price,
+            None => self.processor_price,
         }
     }
+
+    pub fn get_scrap_value(&self, recipe: RecipeHandle) -> f64 {
+        self.get_processor_price(recipe) * self.scrap_value_factor
+    }
+
+    pub fn sell_new_processor(&mut self, recipe: RecipeHandle) -> f64 {
+        let price = self.get_processor_price(recipe);
+        self.manufacturer_revenue += price;
+        price
+    }
+
+    pub fn scrap_processor(&mut self, recipe: RecipeHandle) -> f64 {
+        let refund = self.get_scrap_value(recipe);
+        self.manufacturer_payouts += refund;
+        refund
+    }
+
+    pub fn get_manufacturer_balance(&self) -> f64 {
+        self.manufacturer_revenue - self.manufacturer_payouts
+    }
 }
@@ -6,6 +6,12 @@ pub struct ProducerData {
     pub producers: Vec<Producer>,
 }
 
+impl Default for ProducerData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ProducerData {
     pub fn new() -> ProducerData {
         ProducerData { producers: vec![] }
@@ -7,6 +7,12 @@ pub struct RecipeData {
     pub recipes: Vec<Recipe>,
 }
 
+impl Default for RecipeData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl RecipeData {
     pub fn new() -> RecipeData {
         RecipeData {
@@ -26,4 +32,14 @@ impl RecipeData {
             None
         }
     }
+
+    pub fn get_recipe_handle_by_name(&self, recipe_name: &str) -> Option<RecipeHandle> {
+        for (handle, recipe) in self.recipes.iter().enumerate() {
+            if recipe.name == *recipe_name {
+                return Some(handle);
+            }
+        }
+        log::error!("Recipe '{}' not found!", recipe_name);
+        None
+    }
 }
@@ -0,0 +1,119 @@
+use crate::market::marketplace::Marketplace;
+use crate::market::shipment::Shipment;
+use crate::world_data::market_data::MarketData;
+use serde::{Deserialize, Serialize};
+
+pub type RegionHandle = usize;
+
+// A remote region with a market of its own
+#[derive(Serialize, Deserialize)]
+pub struct Region {
+    pub name: String,
+    pub market_data: MarketData,
+    pub market_place: Marketplace,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct TransportRoute {
+    pub from: RegionHandle,
+    pub to: RegionHandle,
+    pub cost_per_unit: f64,
+    // Ticks until a shipment arrives
+    pub delay: usize,
+}
+
+// The home region has handle 0 and uses the world's market, remote regions are numbered from 1
+#[derive(Serialize, Deserialize)]
+pub struct RegionData {
+    pub home_region_name: String,
+    pub regions: Vec<Region>,
+    pub routes: Vec<TransportRoute>,
+    pub shipments: Vec<Shipment>,
+}
+
+impl Default for RegionData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RegionData {
+    pub fn new() -> RegionData {
+        RegionData {
+            home_region_name: String::from("Home"),
+            regions: vec![],
+            routes: vec![],
+            shipments: vec![],
+        }
+    }
+
+    pub fn get_region_count(&self) -> usize {
+        self.regions.len() + 1
+    }
+
+    pub fn get_region_handle_by_name(&self, name: &str) -> Option<RegionHandle> {
+        if self.home_region_name == name {
+            return Some(0);
+        }
+        self.regions
+            .iter()
+            .position(|region| region.name == name)
+            .map(|index| index + 1)
+    }
+
+    pub fn get_region_name_by_handle(&self, region: RegionHandle) -> Option<&str> {
+        match region {
+            0 => Some(&self.home_region_name),
+            _ => self
+                .regions
+                .get(region - 1)
+                .map(|region| region.name.as_str()),
+        }
+    }
+
+    pub fn get_route(&self, from: RegionHandle, to: RegionHandle) -> Option<&TransportRoute> {
+        self.routes
+            .iter()
+            .find(|route| route.from == from && route.to == to)
+    }
+
+    // Market of a region, unknown regions fall back to the home market
+    pub fn get_market_data<'a>(
+        &'a self,
+        region: RegionHandle,
+        home_market_data: &'a MarketData,
+    ) -> &'a MarketData {
+        match region
+            .checked_sub(1)
+            .and_then(|index| self.regions.get(index))
+        {
+            Some(region) => &region.market_data,
+            None => home_market_data,
+        }
+    }
+
+    // Markets of all regions, starting with the home market
+    pub fn get_all_market_data<'a>(
+        &'a self,
+        home_market_data: &'a MarketData,
+    ) -> impl Iterator<Item = &'a MarketData> {
+        std::iter::once(home_market_data)
+            .chain(self.regions.iter().map(|region| &region.market_data))
+    }
+
+    pub fn get_market<'a>(
+        &'a mut self,
+        region: RegionHandle,
+        home_market_data: &'a mut MarketData,
+        home_market_place: &'a mut Marketplace,
+    ) -> (&'a mut MarketData, &'a mut Marketplace) {
+        let regions = &mut self.regions;
+        match region
+            .checked_sub(1)
+            .and_then(move |index| regions.get_mut(index))
+        {
+            Some(region) => (&mut region.market_data, &mut region.market_place),
+            None => (home_market_data, home_market_place),
+        }
+    }
+}
@@ -1,12 +1,17 @@
 use crate::economy::resource::{Resource, ResourceHandle};
 use serde::{Deserialize, Serialize};
-use simple_logger::SimpleLogger;
 
 #[derive(Serialize, Deserialize)]
 pub struct ResourceData {
     pub resources: Vec<Resource>,
 }
 
+impl Default for ResourceData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ResourceData {
     pub fn new() -> ResourceData {
         ResourceData {
@@ -33,6 +38,13 @@ impl ResourceData {
         }
     }
 
+    pub fn get_resource_category_by_handle(&self, resource_handle: ResourceHandle) -> Option<&str> {
+        match self.resources.get(resource_handle) {
+            Some(resource) => resource.category.as_deref(),
+            None => None,
+        }
+    }
+
     pub fn get_resource_handle_by_name(&self, resource_name: &str) -> Option<ResourceHandle> {
         for (handle, resource) in self.resources.iter().enumerate() {
             if resource.name == *resource_name {
@@ -0,0 +1,156 @@
+use crate::economy::recipe::Recipe;
+use crate::economy::resource::ResourceHandle;
+use crate::economy::stock::Stock;
+use crate::world_data::resource_data::ResourceData;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+pub enum OverflowBehavior {
+    // Processors do not produce if their products would not fit into storage
+    RejectProduction,
+    // Stock exceeding the storage capacity is liquidated at a discount
+    ForcedSale,
+}
+
+// Resources whose category has its own capacity are stored in a separate storage pool, all
+// other resources share the global pool
+#[derive(Serialize, Deserialize)]
+pub struct StorageData {
+    pub capacity: f64,
+    pub category_capacities: HashMap<String, f64>,
+    pub holding_cost_per_unit: f64,
+    pub category_holding_costs: HashMap<String, f64>,
+    pub warehouse_price: f64,
+    // Capacity a single warehouse adds to every storage pool
+    pub warehouse_capacity: f64,
+    pub overflow_behavior: OverflowBehavior,
+    // Fraction of the market price paid for liquidated stock
+    pub forced_sale_price_factor: f64,
+}
+
+impl Default for StorageData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StorageData {
+    pub fn new() -> StorageData {
+        StorageData {
+            capacity: f64::INFINITY,
+            category_capacities: HashMap::new(),
+            holding_cost_per_unit: 0.0,
+            category_holding_costs: HashMap::new(),
+            warehouse_price: 0.0,
+            warehouse_capacity: 0.0,
+            overflow_behavior: OverflowBehavior::RejectProduction,
+            forced_sale_price_factor: 0.5,
+        }
+    }
+
+    pub fn get_storage_pool<'a>(
+        &self,
+        resource: ResourceHandle,
+        resource_data: &'a ResourceData,
+    ) -> Option<&'a str> {
+        match resource_data.get_resource_category_by_handle(resource) {
+            Some(category) if self.category_capacities.contains_key(category) => Some(category),
+            _ => None,
+        }
+    }
+
+    pub fn get_capacity(&self, storage_pool: Option<&str>, warehouses: usize) -> f64 {
+        let base_capacity = match storage_pool {
+            Some(category) => self.category_capacities[category],
+            None => self.capacity,
+        };
+        base_capacity + warehouses as f64 * self.warehouse_capacity
+    }
+
+    pub fn get_used_capacity(
+        &self,
+        storage_pool: Option<&str>,
+        stock: &Stock,
+        resource_data: &ResourceData,
+    ) -> f64 {
+        stock
+            .resources
+            .iter()
+            .filter(|(resource, _)| {
+                self.get_storage_pool(**resource, resource_data) == storage_pool
+            })
+            .map(|(_, amount)| *amount)
+            .sum()
+    }
+
+    pub fn get_free_capacity(
+        &self,
+        storage_pool: Option<&str>,
+        stock: &Stock,
+        resource_data: &ResourceData,
+        warehouses: usize,
+    ) -> f64 {
+        self.get_capacity(storage_pool, warehouses)
+            - self.get_used_capacity(storage_pool, stock, resource_data)
+    }
+
+    pub fn get_storage_pools(&self) -> Vec<Option<&str>> {
+        let mut storage_pools: Vec<Option<&str>> = vec![None];
+        for category in self.category_capacities.keys() {
+            storage_pools.push(Some(category));
+        }
+        storage_pools
+    }
+
+    pub fn can_store_recipe_output(
+        &self,
+        recipe: &Recipe,
+        production_speed: f64,
+        stock: &Stock,
+        resource_data: &ResourceData,
+        warehouses: usize,
+    ) -> bool {
+        // Net change of every storage pool affected by the recipe
+        let mut pool_changes: HashMap<Option<&str>, f64> = HashMap::new();
+        for (resource, amount) in recipe.products.iter() {
+            *pool_changes
+                .entry(self.get_storage_pool(*resource, resource_data))
+                .or_insert(0.0) += amount * production_speed;
+        }
+        for (resource, amount) in recipe.ingredients.iter() {
+            *pool_changes
+                .entry(self.get_storage_pool(*resource, resource_data))
+                .or_insert(0.0) -= amount;
+        }
+        pool_changes.iter().all(|(storage_pool, change)| {
+            *change <= 0.0
+                || *change
+                    <= self.get_free_capacity(*storage_pool, stock, resource_data, warehouses)
+        })
+    }
+
+    pub fn get_holding_cost_per_unit(
+        &self,
+        resource: ResourceHandle,
+        resource_data: &ResourceData,
+    ) -> f64 {
+        match resource_data.get_resource_category_by_handle(resource) {
+            Some(category) => match self.category_holding_costs.get(category) {
+                Some(cost) => *cost,
+                None => self.holding_cost_per_unit,
+            },
+            None => self.holding_cost_per_unit,
+        }
+    }
+
+    pub fn get_holding_costs(&self, stock: &Stock, resource_data: &ResourceData) -> f64 {
+        stock
+            .resources
+            .iter()
+            .map(|(resource, amount)| {
+                amount * self.get_holding_cost_per_unit(*resource, resource_data)
+            })
+            .sum()
+    }
+}